@@ -1,26 +1,162 @@
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::{fmt, fs, io, iter, mem, process};
+
 use bevy::app::PluginGroupBuilder;
 use bevy::core::Stopwatch;
+use bevy::ecs::system::SystemParam;
+use bevy::input::keyboard::KeyboardInput;
+use bevy::input::{ElementState, InputSystem};
 use bevy::prelude::*;
-use bevy::utils::HashMap;
+use bevy::utils::{HashMap, HashSet};
+use bevy::window::WindowMode;
+use clap::{ArgEnum, Parser};
+use ron::ser::PrettyConfig;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::logic::level::{GravityTable, SoftDrop};
+use crate::logic::lock::LockDelay;
+use crate::logic::randomizer::RandomizerKind;
+use crate::logic::score::ScoreTable;
+use crate::stag::game::Replay;
 
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins)
-        .add_plugins(KarpasPlugins)
-        .run();
+    let args = Args::parse();
+
+    let replay = match args.replay.as_deref().map(Replay::load).transpose() {
+        Ok(replay) => replay,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        },
+    };
+
+    let mut window = WindowDescriptor::default();
+    if let Some((width, height)) = args.size {
+        window.width = width;
+        window.height = height;
+    }
+    if args.fullscreen {
+        window.mode = WindowMode::BorderlessFullscreen;
+    }
+
+    let mut app = App::new();
+    app.insert_resource(window);
+
+    if args.headless {
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(bevy::log::LogPlugin)
+            .add_plugin(bevy::transform::TransformPlugin)
+            .add_plugin(bevy::hierarchy::HierarchyPlugin)
+            .add_plugin(bevy::input::InputPlugin)
+            .add_plugin(bevy::window::WindowPlugin::default())
+            .add_plugin(bevy::asset::AssetPlugin);
+    } else {
+        app.add_plugins(DefaultPlugins);
+    }
+
+    app.insert_resource(Launch {
+        config: args.config,
+        // replays are played back from the start
+        stage: match (&replay, args.mode) {
+            (Some(_), _) | (None, Mode::Game) => Stage::Game,
+            (None, Mode::Title) => Stage::Title,
+        },
+        seed: args.seed,
+        replay,
+        record: args.record,
+        headless: args.headless,
+    });
+
+    app.add_plugins(KarpasPlugins).run();
+}
+
+#[derive(Parser)]
+#[clap(version, about)]
+struct Args {
+    /// config file to use instead of the one in the user config directory
+    #[clap(long)]
+    config: Option<PathBuf>,
+    /// where to start
+    #[clap(long, arg_enum, default_value = "title")]
+    mode: Mode,
+    /// seed of the randomizer, instead of the one in the config
+    #[clap(long)]
+    seed: Option<u64>,
+    /// window size as WIDTHxHEIGHT
+    #[clap(long, parse(try_from_str = parse_size))]
+    size: Option<(f32, f32)>,
+    #[clap(long)]
+    fullscreen: bool,
+    /// replay file to play back
+    #[clap(long)]
+    replay: Option<PathBuf>,
+    /// file to save the replay of each game to
+    #[clap(long)]
+    record: Option<PathBuf>,
+    /// plays back the replay without a window, and prints the results
+    #[clap(long, requires = "replay")]
+    headless: bool,
+}
+
+#[derive(Clone, Copy, ArgEnum)]
+enum Mode {
+    Title,
+    Game,
+}
+
+fn parse_size(size: &str) -> Result<(f32, f32), String> {
+    let error = || format!("expected WIDTHxHEIGHT, found \"{}\"", size);
+
+    let (width, height) = size.split_once('x').ok_or_else(error)?;
+    let width = width.parse().map_err(|_| error())?;
+    let height = height.parse().map_err(|_| error())?;
+
+    Ok((width, height))
+}
+
+/// options given from the command line
+struct Launch {
+    config: Option<PathBuf>,
+    /// the stage after loading
+    stage: Stage,
+    seed: Option<u64>,
+    replay: Option<Replay>,
+    record: Option<PathBuf>,
+    headless: bool,
+}
+impl Default for Launch {
+    fn default() -> Self {
+        Self {
+            config: None,
+            stage: Stage::Title,
+            seed: None,
+            replay: None,
+            record: None,
+            headless: false,
+        }
+    }
 }
 
 struct KarpasPlugins;
 impl PluginGroup for KarpasPlugins {
     fn build(&mut self, group: &mut PluginGroupBuilder) {
         group.add(ConfigPlugin);
+        group.add(KeyPlugin);
+        group.add(PadPlugin);
         group.add(StagePlugin);
         group.add(AssetPlugin);
+        group.add(LocalePlugin);
         group.add(LogPlugin);
 
         group.add(stag::initial::Plugin);
         group.add(stag::title::Plugin);
+        group.add(stag::settings::Plugin);
+        group.add(stag::infos::Plugin);
         group.add(stag::game::Plugin);
+        group.add(stag::pause::Plugin);
+        group.add(stag::game_over::Plugin);
         group.add(stag::end::Plugin);
     }
 }
@@ -30,56 +166,482 @@ impl Plugin for ConfigPlugin {
     fn name(&self) -> &str { "config" }
 
     fn build(&self, app: &mut App) {
-        let config = Config {
-            path: PathConfig {
-                font: "fonts/zkgn/ZenKakuGothicNew-Regular.ttf",
-            },
-            key: KeyConfig {
-                title: TitleKeyConfig {
-                    up: KeyCode::K,
-                    down: KeyCode::J,
-                    submit: KeyCode::Return,
-                },
-                game: GameKeyConfig {
-                    left: KeyCode::H,
-                    right: KeyCode::L,
-                    hard_drop: KeyCode::J,
-                    p90_spin: KeyCode::G,
-                    n90_spin: KeyCode::S,
-                },
+        app.init_resource::<Launch>();
+        let launch = app.world.resource::<Launch>();
+
+        let path = match &launch.config {
+            Some(path) => Some(path.clone()),
+            None => dirs::config_dir().map(|dir| dir.join("karpas").join("config.ron")),
+        };
+        let mut file = ConfigFile::new(path);
+
+        let mut config = match file.load() {
+            Ok(config) => config,
+            Err(e) => {
+                error!("{}, using the default config", e);
+                file.writable = false;
+                Config::default()
             },
         };
 
+        // not to save the rules of the replay as the config
+        if let Some(replay) = &launch.replay {
+            config.rule = replay.rule.clone();
+            file.writable = false;
+        }
+
         app.insert_resource(config);
+        app.insert_resource(file);
+    }
+}
+
+/// where the config is loaded from and saved to
+struct ConfigFile {
+    path: Option<PathBuf>,
+    /// not to overwrite a file which could not be loaded
+    writable: bool,
+}
+impl ConfigFile {
+    fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            writable: true,
+        }
+    }
+
+    /// the default config if the file doesn't exist yet
+    fn load(&self) -> Result<Config, FileError> {
+        let Some(path) = &self.path else {
+            return Err(FileError::NoPath);
+        };
+
+        let mut config: Config = match read_ron(path) {
+            Err(FileError::Read(_, e)) if e.kind() == io::ErrorKind::NotFound =>
+                return Ok(Config::default()),
+            result => result?,
+        };
+        config.path.migrate();
+        config
+            .rule
+            .check()
+            .and_then(|()| config.pad.check())
+            .map_err(|e| FileError::Invalid(path.clone(), e))?;
+
+        Ok(config)
+    }
+
+    fn save(&self, config: &Config) -> Result<(), FileError> {
+        let Some(path) = &self.path else {
+            return Err(FileError::NoPath);
+        };
+        if !self.writable {
+            return Err(FileError::NotWritable(path.clone()));
+        }
+
+        write_ron(path, config)
+    }
+}
+
+fn read_ron<T: DeserializeOwned>(path: &Path) -> Result<T, FileError> {
+    let text = fs::read_to_string(path).map_err(|e| FileError::Read(path.to_path_buf(), e))?;
+
+    ron::from_str(&text).map_err(|e| FileError::Parse(path.to_path_buf(), e))
+}
+
+fn write_ron<T: Serialize>(path: &Path, value: &T) -> Result<(), FileError> {
+    let text =
+        ron::ser::to_string_pretty(value, PrettyConfig::default()).map_err(FileError::Serialize)?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| FileError::Write(path.to_path_buf(), e))?;
+    }
+    fs::write(path, text).map_err(|e| FileError::Write(path.to_path_buf(), e))
+}
+
+#[derive(Debug)]
+enum FileError {
+    NoPath,
+    Read(PathBuf, io::Error),
+    Parse(PathBuf, ron::Error),
+    /// parsed, but not playable
+    Invalid(PathBuf, &'static str),
+    Serialize(ron::Error),
+    Write(PathBuf, io::Error),
+    NotWritable(PathBuf),
+}
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoPath => write!(f, "no file to use"),
+            Self::Read(path, e) => write!(f, "failed to read {}: {}", path.display(), e),
+            Self::Parse(path, e) => write!(f, "malformed {}: {}", path.display(), e),
+            Self::Invalid(path, e) => write!(f, "invalid {}: {}", path.display(), e),
+            Self::Serialize(e) => write!(f, "failed to serialize the config: {}", e),
+            Self::Write(path, e) => write!(f, "failed to write {}: {}", path.display(), e),
+            Self::NotWritable(path) => {
+                write!(
+                    f,
+                    "not to overwrite {}, which failed to load",
+                    path.display()
+                )
+            },
+        }
     }
 }
 
+// missing fields are filled with the defaults
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
 struct Config {
+    language: Language,
     path: PathConfig,
     key: KeyConfig,
+    pad: PadConfig,
+    rule: RuleConfig,
+    view: ViewConfig,
+    handling: HandlingConfig,
+    theme: ThemeConfig,
 }
 
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
 struct PathConfig {
-    font: &'static str,
+    fonts: FontPathConfig,
+    /// the single font of older configs, read as the regular weight
+    #[serde(skip_serializing)]
+    font: String,
+}
+impl PathConfig {
+    fn migrate(&mut self) {
+        if !self.font.is_empty() {
+            let font = mem::take(&mut self.font);
+            warn!("`path.font` is deprecated, using it as `path.fonts.regular`");
+            self.fonts.regular = font;
+        }
+    }
+}
+
+/// a path for each weight of `FontKey`
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct FontPathConfig {
+    light: String,
+    regular: String,
+    medium: String,
+    bold: String,
+    black: String,
+}
+impl FontPathConfig {
+    fn get(&self, key: FontKey) -> &str {
+        match key {
+            FontKey::Light => &self.light,
+            FontKey::Regular => &self.regular,
+            FontKey::Medium => &self.medium,
+            FontKey::Bold => &self.bold,
+            FontKey::Black => &self.black,
+        }
+    }
 }
+impl Default for FontPathConfig {
+    fn default() -> Self {
+        let path = |weight| format!("fonts/zkgn/ZenKakuGothicNew-{}.ttf", weight);
+
+        Self {
+            light: path("Light"),
+            regular: path("Regular"),
+            medium: path("Medium"),
+            bold: path("Bold"),
+            black: path("Black"),
+        }
+    }
+}
+
+/// bindings of the keyboard, or of a gamepad with `PadButton`
+#[derive(Serialize, Deserialize)]
+#[serde(
+    default,
+    bound(
+        deserialize = "B: Deserialize<'de>, TitleKeyConfig<B>: Default, GameKeyConfig<B>: Default"
+    )
+)]
+struct KeyConfig<B = KeyCode> {
+    title: TitleKeyConfig<B>,
+    game: GameKeyConfig<B>,
+}
+impl<B> Default for KeyConfig<B>
+where
+    TitleKeyConfig<B>: Default,
+    GameKeyConfig<B>: Default,
+{
+    fn default() -> Self {
+        Self {
+            title: TitleKeyConfig::default(),
+            game: GameKeyConfig::default(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(
+    default,
+    bound(deserialize = "B: Deserialize<'de>, TitleKeyConfig<B>: Default")
+)]
+struct TitleKeyConfig<B> {
+    up: B,
+    down: B,
+    submit: B,
+    /// leaves the stages pushed over the title
+    back: B,
+}
+impl Default for TitleKeyConfig<KeyCode> {
+    fn default() -> Self {
+        Self {
+            up: KeyCode::K,
+            down: KeyCode::J,
+            submit: KeyCode::Return,
+            back: KeyCode::Escape,
+        }
+    }
+}
+impl Default for TitleKeyConfig<PadButton> {
+    fn default() -> Self {
+        Self {
+            up: PadButton::DPadUp,
+            down: PadButton::DPadDown,
+            submit: PadButton::South,
+            back: PadButton::East,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(
+    default,
+    bound(deserialize = "B: Deserialize<'de>, GameKeyConfig<B>: Default")
+)]
+struct GameKeyConfig<B> {
+    left: B,
+    right: B,
+    /// held to fall faster
+    soft_drop: B,
+    hard_drop: B,
+    p90_spin: B,
+    n90_spin: B,
+    /// 180 spins are disabled without a key
+    p180_spin: Option<B>,
+    hold: B,
+    pause: B,
+}
+impl Default for GameKeyConfig<KeyCode> {
+    fn default() -> Self {
+        Self {
+            left: KeyCode::H,
+            right: KeyCode::L,
+            soft_drop: KeyCode::D,
+            hard_drop: KeyCode::J,
+            p90_spin: KeyCode::G,
+            n90_spin: KeyCode::S,
+            p180_spin: None,
+            hold: KeyCode::A,
+            pause: KeyCode::Escape,
+        }
+    }
+}
+impl Default for GameKeyConfig<PadButton> {
+    fn default() -> Self {
+        Self {
+            left: PadButton::DPadLeft,
+            right: PadButton::DPadRight,
+            soft_drop: PadButton::DPadDown,
+            hard_drop: PadButton::DPadUp,
+            p90_spin: PadButton::East,
+            n90_spin: PadButton::South,
+            p180_spin: Some(PadButton::North),
+            hold: PadButton::LeftTrigger,
+            pause: PadButton::Start,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct PadConfig {
+    /// per gamepad slot, numbered in the order the gamepads are connected,
+    /// since gamepads can't be told apart. a gamepad may take another slot
+    /// once reconnected
+    profiles: Vec<KeyConfig<PadButton>>,
+    /// how far the sticks are tilted to press, over 0 and up to 1
+    stick_threshold: f32,
+    /// for the slots without a profile
+    #[serde(skip)]
+    default_profile: KeyConfig<PadButton>,
+}
+impl Default for PadConfig {
+    fn default() -> Self {
+        Self {
+            profiles: Vec::new(),
+            stick_threshold: 0.5,
+            default_profile: KeyConfig::default(),
+        }
+    }
+}
+impl PadConfig {
+    fn profile(&self, gamepad: Gamepad) -> &KeyConfig<PadButton> {
+        self.profiles
+            .get(gamepad.0)
+            .unwrap_or(&self.default_profile)
+    }
+
+    fn check(&self) -> Result<(), &'static str> {
+        // an idle stick would press otherwise
+        if !(self.stick_threshold > 0.0 && self.stick_threshold <= 1.0) {
+            return Err("the stick threshold must be in (0, 1]");
+        }
 
-struct KeyConfig {
-    title: TitleKeyConfig,
-    game: GameKeyConfig,
+        Ok(())
+    }
+
+    /// added with the defaults if missing, when first edited
+    fn profile_mut(&mut self, gamepad: Gamepad) -> &mut KeyConfig<PadButton> {
+        if self.profiles.len() <= gamepad.0 {
+            self.profiles.resize_with(gamepad.0 + 1, KeyConfig::default);
+        }
+        &mut self.profiles[gamepad.0]
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct RuleConfig {
+    score: ScoreTable,
+    gravity: GravityTable,
+    soft_drop: SoftDrop,
+    lock_delay: LockDelay,
+    lines_per_level: u32,
+    randomizer: RandomizerKind,
+    /// fixed to reproduce a session, or random for each game
+    seed: Option<u64>,
+    /// up to 6
+    previews: usize,
+}
+impl Default for RuleConfig {
+    fn default() -> Self {
+        Self {
+            score: ScoreTable::default(),
+            gravity: GravityTable::default(),
+            soft_drop: SoftDrop::Factor(20.0),
+            lock_delay: LockDelay {
+                delay: 500,
+                resets: Some(15),
+            },
+            lines_per_level: 10,
+            randomizer: RandomizerKind::Bag7,
+            seed: None,
+            previews: 5,
+        }
+    }
+}
+impl RuleConfig {
+    /// the gravity is already checked when parsed
+    fn check(&self) -> Result<(), &'static str> {
+        if let SoftDrop::Factor(factor) = self.soft_drop {
+            if !factor.is_finite() || factor <= 0.0 {
+                return Err("the soft drop factor must be positive");
+            }
+        }
+        if self.score.back_to_back.1 == 0 {
+            return Err("the back-to-back denominator must not be 0");
+        }
+
+        Ok(())
+    }
 }
 
-struct TitleKeyConfig {
-    up: KeyCode,
-    down: KeyCode,
-    submit: KeyCode,
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct ViewConfig {
+    /// where the falling piece would land by a hard drop
+    ghost: bool,
+}
+impl Default for ViewConfig {
+    fn default() -> Self { Self { ghost: true } }
+}
+
+/// fonts and sizes of texts by their roles
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct ThemeConfig {
+    heading: TextRole,
+    /// items of the title menu, selected in the font of `selected`
+    title: TextRole,
+    /// menu items but the selected one
+    item: TextRole,
+    selected: TextRole,
+    score: TextRole,
+    /// sections, levels and results
+    label: TextRole,
+    /// rows of settings and controls, versions, messages and credits
+    small: TextRole,
+}
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        Self {
+            heading: TextRole::new(FontKey::Bold, 64.0),
+            title: TextRole::new(FontKey::Regular, 64.0),
+            item: TextRole::new(FontKey::Regular, 48.0),
+            selected: TextRole::new(FontKey::Bold, 48.0),
+            score: TextRole::new(FontKey::Medium, 48.0),
+            label: TextRole::new(FontKey::Regular, 32.0),
+            small: TextRole::new(FontKey::Light, 24.0),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct TextRole {
+    font: FontKey,
+    size: f32,
+}
+impl TextRole {
+    fn new(font: FontKey, size: f32) -> Self { Self { font, size } }
+
+    fn style(&self, assets: &AssetStore, color: Color) -> TextStyle {
+        TextStyle {
+            font: assets.font(self.font),
+            font_size: self.size,
+            color,
+        }
+    }
+
+    /// only the font and the size, to keep the color
+    fn apply(&self, assets: &AssetStore, style: &mut TextStyle) {
+        style.font = assets.font(self.font);
+        style.font_size = self.size;
+    }
 }
 
-struct GameKeyConfig {
-    left: KeyCode,
-    right: KeyCode,
-    hard_drop: KeyCode,
-    p90_spin: KeyCode,
-    n90_spin: KeyCode,
+/// in milliseconds
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct HandlingConfig {
+    /// delay before held moves start to repeat
+    das: u64,
+    /// interval of repeated moves, 0 moves to the wall at once
+    arr: u64,
+    /// delay the repeat after spins by, 0 not to delay
+    das_cut: u64,
+    /// soft drops repeat as fast as the soft drop factor
+    soft_drop_das: u64,
+}
+impl Default for HandlingConfig {
+    fn default() -> Self {
+        Self {
+            das: 167,
+            arr: 33,
+            das_cut: 0,
+            soft_drop_das: 0,
+        }
+    }
 }
 
 struct StagePlugin;
@@ -111,8 +673,10 @@ fn end_on_3_esc_press(
 
         stopwatch.reset();
 
+        // the last press may also pause or top out the game in this frame,
+        // so quitting overwrites whatever is queued
         if *count >= 3 {
-            stage.replace(Stage::End).unwrap();
+            stage.overwrite_replace(Stage::End).unwrap();
         }
     }
 
@@ -122,6 +686,416 @@ fn end_on_3_esc_press(
     }
 }
 
+/// actions to bind keys and gamepad buttons to
+#[derive(Clone, Copy, PartialEq)]
+enum Binding {
+    TitleUp,
+    TitleDown,
+    TitleSubmit,
+    TitleBack,
+    Left,
+    Right,
+    SoftDrop,
+    HardDrop,
+    P90Spin,
+    N90Spin,
+    P180Spin,
+    Hold,
+    Pause,
+}
+impl Binding {
+    const ALL: [Self; 13] = [
+        Self::TitleUp,
+        Self::TitleDown,
+        Self::TitleSubmit,
+        Self::TitleBack,
+        Self::Left,
+        Self::Right,
+        Self::SoftDrop,
+        Self::HardDrop,
+        Self::P90Spin,
+        Self::N90Spin,
+        Self::P180Spin,
+        Self::Hold,
+        Self::Pause,
+    ];
+
+    fn message(&self) -> Message {
+        match *self {
+            Self::TitleUp => Message::MenuUp,
+            Self::TitleDown => Message::MenuDown,
+            Self::TitleSubmit => Message::MenuSubmit,
+            Self::TitleBack => Message::MenuBack,
+            Self::Left => Message::Left,
+            Self::Right => Message::Right,
+            Self::SoftDrop => Message::SoftDrop,
+            Self::HardDrop => Message::HardDrop,
+            Self::P90Spin => Message::SpinLeft,
+            Self::N90Spin => Message::SpinRight,
+            Self::P180Spin => Message::Spin180,
+            Self::Hold => Message::Hold,
+            Self::Pause => Message::Pause,
+        }
+    }
+
+    /// bindings in the same group must not share a key
+    fn is_in_title(&self) -> bool {
+        matches!(
+            *self,
+            Self::TitleUp | Self::TitleDown | Self::TitleSubmit | Self::TitleBack
+        )
+    }
+
+    fn is_optional(&self) -> bool { matches!(*self, Self::P180Spin) }
+
+    fn get<B: Copy>(&self, config: &KeyConfig<B>) -> Option<B> {
+        let (title, game) = (&config.title, &config.game);
+
+        match *self {
+            Self::TitleUp => Some(title.up),
+            Self::TitleDown => Some(title.down),
+            Self::TitleSubmit => Some(title.submit),
+            Self::TitleBack => Some(title.back),
+            Self::Left => Some(game.left),
+            Self::Right => Some(game.right),
+            Self::SoftDrop => Some(game.soft_drop),
+            Self::HardDrop => Some(game.hard_drop),
+            Self::P90Spin => Some(game.p90_spin),
+            Self::N90Spin => Some(game.n90_spin),
+            Self::P180Spin => game.p180_spin,
+            Self::Hold => Some(game.hold),
+            Self::Pause => Some(game.pause),
+        }
+    }
+
+    /// `None` is only for optional bindings
+    fn set<B>(&self, config: &mut KeyConfig<B>, key: Option<B>) {
+        let (title, game) = (&mut config.title, &mut config.game);
+
+        if let Self::P180Spin = *self {
+            game.p180_spin = key;
+            return;
+        }
+
+        let key = key.unwrap();
+        match *self {
+            Self::TitleUp => title.up = key,
+            Self::TitleDown => title.down = key,
+            Self::TitleSubmit => title.submit = key,
+            Self::TitleBack => title.back = key,
+            Self::Left => game.left = key,
+            Self::Right => game.right = key,
+            Self::SoftDrop => game.soft_drop = key,
+            Self::HardDrop => game.hard_drop = key,
+            Self::P90Spin => game.p90_spin = key,
+            Self::N90Spin => game.n90_spin = key,
+            Self::Hold => game.hold = key,
+            Self::Pause => game.pause = key,
+            Self::P180Spin => unreachable!(),
+        }
+    }
+
+    /// the key, and the button of the gamepad if any
+    fn text(&self, config: &Config, locale: &Locale, pad: Option<Gamepad>) -> String {
+        fn name<B: fmt::Debug>(button: Option<B>) -> String {
+            match button {
+                Some(button) => format!("{:?}", button),
+                None => "-".to_string(),
+            }
+        }
+
+        let label = locale.get(self.message());
+        let key = name(self.get(&config.key));
+        match pad.map(|pad| config.pad.profile(pad)) {
+            Some(profile) => format!("{}  {}  {}", label, key, name(self.get(profile))),
+            None => format!("{}  {}", label, key),
+        }
+    }
+
+    /// the other binding in the group already using the key
+    fn conflict<B: Copy + PartialEq>(&self, config: &KeyConfig<B>, key: B) -> Option<Self> {
+        Self::ALL.into_iter().find(|other| {
+            *other != *self
+                && other.is_in_title() == self.is_in_title()
+                && other.get(config) == Some(key)
+        })
+    }
+}
+
+/// gamepad buttons, and the left stick tilted as buttons
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+enum PadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftTrigger,
+    LeftTrigger2,
+    RightTrigger,
+    RightTrigger2,
+    Select,
+    Start,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    StickUp,
+    StickDown,
+    StickLeft,
+    StickRight,
+}
+impl PadButton {
+    const ALL: [Self; 20] = [
+        Self::South,
+        Self::East,
+        Self::North,
+        Self::West,
+        Self::LeftTrigger,
+        Self::LeftTrigger2,
+        Self::RightTrigger,
+        Self::RightTrigger2,
+        Self::Select,
+        Self::Start,
+        Self::LeftThumb,
+        Self::RightThumb,
+        Self::DPadUp,
+        Self::DPadDown,
+        Self::DPadLeft,
+        Self::DPadRight,
+        Self::StickUp,
+        Self::StickDown,
+        Self::StickLeft,
+        Self::StickRight,
+    ];
+
+    /// the button read for this, if not only a stick
+    fn button_type(&self) -> Option<GamepadButtonType> {
+        use GamepadButtonType as B;
+
+        match *self {
+            Self::South => Some(B::South),
+            Self::East => Some(B::East),
+            Self::North => Some(B::North),
+            Self::West => Some(B::West),
+            Self::LeftTrigger => Some(B::LeftTrigger),
+            Self::LeftTrigger2 => Some(B::LeftTrigger2),
+            Self::RightTrigger => Some(B::RightTrigger),
+            Self::RightTrigger2 => Some(B::RightTrigger2),
+            Self::Select => Some(B::Select),
+            Self::Start => Some(B::Start),
+            Self::LeftThumb => Some(B::LeftThumb),
+            Self::RightThumb => Some(B::RightThumb),
+            Self::DPadUp => Some(B::DPadUp),
+            Self::DPadDown => Some(B::DPadDown),
+            Self::DPadLeft => Some(B::DPadLeft),
+            Self::DPadRight => Some(B::DPadRight),
+            Self::StickUp | Self::StickDown | Self::StickLeft | Self::StickRight => None,
+        }
+    }
+
+    fn is_pressed(
+        &self,
+        gamepad: Gamepad,
+        buttons: &Input<GamepadButton>,
+        axes: &Axis<GamepadAxis>,
+        threshold: f32,
+    ) -> bool {
+        use GamepadAxisType as A;
+
+        let button = self
+            .button_type()
+            .is_some_and(|button_type| buttons.pressed(GamepadButton(gamepad, button_type)));
+        let axis = |axis_type| axes.get(GamepadAxis(gamepad, axis_type)).unwrap_or(0.0);
+
+        match *self {
+            // some gamepads report the d-pad as axes
+            Self::DPadUp => button || axis(A::DPadY) >= threshold,
+            Self::DPadDown => button || axis(A::DPadY) <= -threshold,
+            Self::DPadLeft => button || axis(A::DPadX) <= -threshold,
+            Self::DPadRight => button || axis(A::DPadX) >= threshold,
+            Self::StickUp => axis(A::LeftStickY) >= threshold,
+            Self::StickDown => axis(A::LeftStickY) <= -threshold,
+            Self::StickLeft => axis(A::LeftStickX) <= -threshold,
+            Self::StickRight => axis(A::LeftStickX) >= threshold,
+            _ => button,
+        }
+    }
+
+    /// pressed and released since the last frame, which `is_pressed` misses
+    fn is_tapped(&self, gamepad: Gamepad, buttons: &Input<GamepadButton>) -> bool {
+        self.button_type().is_some_and(|button_type| {
+            let button = GamepadButton(gamepad, button_type);
+            buttons.just_pressed(button) && !buttons.pressed(button)
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct PadInput(Gamepad, PadButton);
+
+struct PadPlugin;
+impl Plugin for PadPlugin {
+    fn name(&self) -> &str { "pad" }
+
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Input<PadInput>>();
+        app.add_system_to_stage(CoreStage::PreUpdate, update_pad_input.after(InputSystem));
+    }
+}
+
+// read like the keys, as `Input<KeyCode>` is
+fn update_pad_input(
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    config: Res<Config>,
+    mut pad: ResMut<Input<PadInput>>,
+) {
+    pad.clear();
+
+    let disconnected: Vec<_> = pad
+        .get_pressed()
+        .filter(|input| !gamepads.contains(&input.0))
+        .copied()
+        .collect();
+    for input in disconnected {
+        pad.release(input);
+    }
+
+    for gamepad in gamepads.iter() {
+        for button in PadButton::ALL {
+            let input = PadInput(*gamepad, button);
+            let pressed = button.is_pressed(*gamepad, &buttons, &axes, config.pad.stick_threshold);
+
+            match (pressed, pad.pressed(input)) {
+                (true, false) => pad.press(input),
+                (false, true) => pad.release(input),
+                // just pressed, as a key tapped within a frame is
+                (false, false) if button.is_tapped(*gamepad, &buttons) => {
+                    pad.press(input);
+                    pad.release(input);
+                },
+                _ => (),
+            }
+        }
+    }
+}
+
+struct KeyPlugin;
+impl Plugin for KeyPlugin {
+    fn name(&self) -> &str { "key" }
+
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KeyPresses>();
+        app.add_system_to_stage(CoreStage::PreUpdate, count_key_presses.after(InputSystem));
+    }
+}
+
+/// presses of each key since the last frame, as `Input<KeyCode>` tells only
+/// whether there was one
+#[derive(Default)]
+struct KeyPresses(HashMap<KeyCode, usize>);
+
+fn count_key_presses(
+    mut events: EventReader<KeyboardInput>,
+    mut held: Local<HashSet<KeyCode>>,
+    mut presses: ResMut<KeyPresses>,
+) {
+    presses.0.clear();
+
+    for event in events.iter() {
+        let Some(key) = event.key_code else {
+            continue;
+        };
+
+        match event.state {
+            // not the repeats of the os
+            ElementState::Pressed =>
+                if held.insert(key) {
+                    *presses.0.entry(key).or_default() += 1;
+                },
+            ElementState::Released => {
+                held.remove(&key);
+            },
+        }
+    }
+}
+
+/// the keyboard and the gamepads, read through the bindings
+#[derive(SystemParam)]
+struct Controls<'w, 's> {
+    key: Res<'w, Input<KeyCode>>,
+    key_presses: Res<'w, KeyPresses>,
+    pad: Res<'w, Input<PadInput>>,
+    config: Res<'w, Config>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+impl Controls<'_, '_> {
+    fn is_bound(&self, binding: Binding, input: &PadInput) -> bool {
+        binding.get(self.config.pad.profile(input.0)) == Some(input.1)
+    }
+
+    fn pressed(&self, binding: Binding) -> bool {
+        binding
+            .get(&self.config.key)
+            .is_some_and(|key| self.key.pressed(key))
+            || self
+                .pad
+                .get_pressed()
+                .any(|input| self.is_bound(binding, input))
+    }
+
+    /// presses in this frame, counting taps released in the same frame
+    fn presses(&self, binding: Binding) -> usize {
+        let key = binding
+            .get(&self.config.key)
+            .and_then(|key| self.key_presses.0.get(&key).copied())
+            .unwrap_or(0);
+        let pad = self
+            .pad
+            .get_just_pressed()
+            .filter(|input| self.is_bound(binding, input))
+            .count();
+
+        key + pad
+    }
+
+    fn any_just_pressed(&self, bindings: impl IntoIterator<Item = Binding>) -> bool {
+        bindings.into_iter().any(|binding| {
+            binding
+                .get(&self.config.key)
+                .is_some_and(|key| self.key.just_pressed(key))
+                || self
+                    .pad
+                    .get_just_pressed()
+                    .any(|input| self.is_bound(binding, input))
+        })
+    }
+
+    /// inputs bound to the keys or buttons just pressed, once a press, in the
+    /// order of `bindings`
+    fn just_pressed<T: Copy>(&self, bindings: &[(Binding, T)]) -> Vec<T> {
+        bindings
+            .iter()
+            .flat_map(|(binding, input)| iter::repeat_n(*input, self.presses(*binding)))
+            .collect()
+    }
+}
+
+// not to let the next stage take over the press which caused the transition
+fn clear_input(
+    mut key: ResMut<Input<KeyCode>>,
+    mut key_presses: ResMut<KeyPresses>,
+    mut pad: ResMut<Input<PadInput>>,
+) {
+    key.clear();
+    key_presses.0.clear();
+    pad.clear();
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 enum Stage {
     Initial,
@@ -129,6 +1103,9 @@ enum Stage {
     Settings,
     Infos,
     Game,
+    /// pushed over the game
+    Pause,
+    GameOver,
     End,
 }
 
@@ -137,14 +1114,234 @@ impl Plugin for AssetPlugin {
     fn name(&self) -> &str { "asset" }
 
     fn build(&self, app: &mut App) {
-        app.insert_resource(AssetStore {
-            store: HashMap::new(),
-        });
+        let manifest = Manifest::new(app.world.resource::<Config>());
+
+        app.insert_resource(manifest);
+        app.init_resource::<AssetStore>();
+    }
+}
+
+/// weights of the bundled font to get from `AssetStore`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+enum FontKey {
+    Light,
+    Regular,
+    Medium,
+    Bold,
+    Black,
+}
+impl FontKey {
+    const ALL: [Self; 5] = [
+        Self::Light,
+        Self::Regular,
+        Self::Medium,
+        Self::Bold,
+        Self::Black,
+    ];
+}
+
+/// assets loaded in the initial stage, with the paths
+struct Manifest {
+    fonts: Vec<(FontKey, String)>,
+}
+impl Manifest {
+    // textures and sounds are to be listed alike, with keys of their own types
+    fn new(config: &Config) -> Self {
+        Self {
+            fonts: FontKey::ALL
+                .into_iter()
+                .map(|key| (key, config.path.fonts.get(key).to_string()))
+                .collect(),
+        }
     }
 }
 
+/// strong handles of the assets in the manifest
+#[derive(Default)]
 struct AssetStore {
-    store: HashMap<&'static str, HandleUntyped>,
+    fonts: HashMap<FontKey, Handle<Font>>,
+}
+impl AssetStore {
+    /// the initial stage makes sure that all keys are loaded
+    fn font(&self, key: FontKey) -> Handle<Font> { self.fonts[&key].clone() }
+}
+
+struct LocalePlugin;
+impl Plugin for LocalePlugin {
+    fn name(&self) -> &str { "locale" }
+
+    fn build(&self, app: &mut App) {
+        let locale = Locale::new(app.world.resource::<Config>().language);
+
+        app.insert_resource(locale);
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+enum Language {
+    #[default]
+    En,
+    Ja,
+}
+impl Language {
+    fn catalog(&self) -> &'static str {
+        match *self {
+            Self::En => include_str!("../assets/locales/en.ron"),
+            Self::Ja => include_str!("../assets/locales/ja.ron"),
+        }
+    }
+}
+
+/// keys of the catalogs, where `{0}`, `{1}` and so on are replaced by the
+/// arguments
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+enum Message {
+    Start,
+    Settings,
+    Infos,
+    Exit,
+    FailedToLoad,
+
+    MenuUp,
+    MenuDown,
+    MenuSubmit,
+    MenuBack,
+    Left,
+    Right,
+    SoftDrop,
+    HardDrop,
+    SpinLeft,
+    SpinRight,
+    Spin180,
+    Hold,
+    Pause,
+
+    Das,
+    Arr,
+    DasCut,
+    SoftDropDas,
+    Previews,
+    Back,
+    Cancelled,
+    Unbound,
+    AlreadyBound,
+    Swapped,
+    CaptureKey,
+    CaptureOptionalKey,
+    EditValue,
+
+    Version,
+    Controls,
+    Credits,
+
+    LevelCounter,
+    Paused,
+    Resume,
+    Restart,
+    Title,
+    Quit,
+
+    GameOver,
+    Retry,
+    Score,
+    Lines,
+    Level,
+    Time,
+    Pieces,
+    Seed,
+}
+impl Message {
+    #[cfg(test)]
+    const ALL: [Self; 48] = [
+        Self::Start,
+        Self::Settings,
+        Self::Infos,
+        Self::Exit,
+        Self::FailedToLoad,
+        Self::MenuUp,
+        Self::MenuDown,
+        Self::MenuSubmit,
+        Self::MenuBack,
+        Self::Left,
+        Self::Right,
+        Self::SoftDrop,
+        Self::HardDrop,
+        Self::SpinLeft,
+        Self::SpinRight,
+        Self::Spin180,
+        Self::Hold,
+        Self::Pause,
+        Self::Das,
+        Self::Arr,
+        Self::DasCut,
+        Self::SoftDropDas,
+        Self::Previews,
+        Self::Back,
+        Self::Cancelled,
+        Self::Unbound,
+        Self::AlreadyBound,
+        Self::Swapped,
+        Self::CaptureKey,
+        Self::CaptureOptionalKey,
+        Self::EditValue,
+        Self::Version,
+        Self::Controls,
+        Self::Credits,
+        Self::LevelCounter,
+        Self::Paused,
+        Self::Resume,
+        Self::Restart,
+        Self::Title,
+        Self::Quit,
+        Self::GameOver,
+        Self::Retry,
+        Self::Score,
+        Self::Lines,
+        Self::Level,
+        Self::Time,
+        Self::Pieces,
+        Self::Seed,
+    ];
+}
+
+/// messages in the language of the config
+struct Locale {
+    messages: HashMap<Message, String>,
+    /// for the messages missing in the catalog
+    fallback: HashMap<Message, String>,
+}
+impl Locale {
+    fn new(language: Language) -> Self {
+        Self {
+            messages: catalog(language),
+            fallback: catalog(Language::En),
+        }
+    }
+
+    fn get(&self, message: Message) -> String { self.format(message, &[]) }
+
+    fn format(&self, message: Message, args: &[&dyn fmt::Display]) -> String {
+        let Some(text) = self
+            .messages
+            .get(&message)
+            .or_else(|| self.fallback.get(&message))
+        else {
+            return format!("{:?}", message);
+        };
+
+        args.iter()
+            .enumerate()
+            .fold(text.clone(), |text, (i, arg)| {
+                text.replace(&format!("{{{}}}", i), &arg.to_string())
+            })
+    }
+}
+
+fn catalog(language: Language) -> HashMap<Message, String> {
+    ron::from_str(language.catalog()).unwrap_or_else(|e| {
+        error!("malformed catalog of {:?}: {}", language, e);
+        HashMap::default()
+    })
 }
 
 struct LogPlugin;
@@ -175,41 +1372,246 @@ fn on_stage_changed(mut before: Local<Option<Stage>>, stage: Res<State<Stage>>)
 
 mod stag {
     pub mod initial {
+        use std::iter;
+
         use bevy::app::Plugin as PluginTrait;
+        use bevy::asset::LoadState;
         use bevy::prelude::*;
 
         use crate::Stage::Initial as SelfStage;
-        use crate::{AssetStore, Config, Stage};
+        use crate::{AssetStore, Config, FontKey, Launch, Locale, Manifest, Message, Stage};
 
         pub struct Plugin;
         impl PluginTrait for Plugin {
             fn name(&self) -> &str { "initial" }
 
             fn build(&self, app: &mut App) {
-                app.add_event::<Loaded>();
-                app.add_system_set(SystemSet::on_enter(SelfStage).with_system(load_assets));
-                app.add_system_set(SystemSet::on_update(SelfStage).with_system(detect_loaded));
+                app.add_event::<Failed>();
+                app.add_system_set(
+                    SystemSet::on_enter(SelfStage)
+                        .with_system(load_assets)
+                        .with_system(spawn_ui),
+                );
+                app.add_system_set(
+                    SystemSet::on_update(SelfStage)
+                        .with_system(detect_loaded)
+                        .with_system(show_failure),
+                );
+                app.add_system_set(SystemSet::on_exit(SelfStage).with_system(despawn_ui));
             }
         }
 
-        struct Loaded;
+        /// with the path of the asset
+        struct Failed(String);
 
         fn load_assets(
             asset_server: Res<AssetServer>,
-            config: Res<Config>,
+            manifest: Res<Manifest>,
             mut store: ResMut<AssetStore>,
-            mut loaded: EventWriter<Loaded>,
         ) {
-            store
-                .store
-                .insert("font-zen", asset_server.load_untyped(config.path.font));
+            for (key, path) in manifest.fonts.iter() {
+                store.fonts.insert(*key, asset_server.load(path.as_str()));
+            }
+        }
+
+        // no text before the font is loaded
+        fn spawn_ui(mut commands: Commands) {
+            commands
+                .spawn()
+                .insert(UiEntity)
+                .insert_bundle(UiCameraBundle::default());
+
+            commands
+                .spawn()
+                .insert(UiEntity)
+                .insert_bundle(NodeBundle {
+                    style: Style {
+                        size: Size {
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(100.0),
+                        },
+                        flex_direction: FlexDirection::ColumnReverse,
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    color: UiColor(Color::NONE),
+                    ..default()
+                })
+                .with_children(|cb| {
+                    cb.spawn()
+                        .insert(UiEntity)
+                        .insert_bundle(NodeBundle {
+                            style: Style {
+                                size: Size {
+                                    width: Val::Px(320.0),
+                                    height: Val::Px(8.0),
+                                },
+                                ..default()
+                            },
+                            color: UiColor(Color::DARK_GRAY),
+                            ..default()
+                        })
+                        .with_children(|cb| {
+                            cb.spawn()
+                                .insert(UiEntity)
+                                .insert(ProgressEntity)
+                                .insert_bundle(NodeBundle {
+                                    style: Style {
+                                        size: Size {
+                                            width: Val::Percent(0.0),
+                                            height: Val::Percent(100.0),
+                                        },
+                                        ..default()
+                                    },
+                                    color: UiColor(Color::ANTIQUE_WHITE),
+                                    ..default()
+                                });
+                        });
+                });
+        }
+
+        #[derive(Component)]
+        struct UiEntity;
+
+        #[derive(Component)]
+        struct ProgressEntity;
+
+        #[allow(clippy::too_many_arguments)]
+        fn detect_loaded(
+            mut failed: Local<bool>,
+            asset_server: Res<AssetServer>,
+            manifest: Res<Manifest>,
+            store: Res<AssetStore>,
+            launch: Res<Launch>,
+            mut stage: ResMut<State<Stage>>,
+            mut progresses: Query<&mut Style, With<ProgressEntity>>,
+            mut failures: EventWriter<Failed>,
+        ) {
+            // the loaders are missing without the default plugins, and nothing is shown
+            if launch.headless {
+                stage.set(launch.stage).unwrap();
+                return;
+            }
+
+            if *failed {
+                return;
+            }
+
+            let count = |state| {
+                store
+                    .fonts
+                    .values()
+                    .filter(|handle| asset_server.get_load_state(*handle) == state)
+                    .count()
+            };
+            let (loaded, failures_count) = (count(LoadState::Loaded), count(LoadState::Failed));
+
+            // the failure is shown after the others are done, to show it with them
+            if loaded + failures_count < manifest.fonts.len() {
+                let ratio = loaded as f32 / manifest.fonts.len() as f32;
+
+                for mut style in progresses.iter_mut() {
+                    style.size.width = Val::Percent(100.0 * ratio);
+                }
+                return;
+            }
+
+            if failures_count == 0 {
+                stage.set(launch.stage).unwrap();
+                return;
+            }
+
+            *failed = true;
+            for (key, path) in manifest.fonts.iter() {
+                if asset_server.get_load_state(&store.fonts[key]) == LoadState::Failed {
+                    error!("failed to load {}", path);
+                    failures.send(Failed(path.clone()));
+                }
+            }
+        }
+
+        // stays in the stage, as the following stages need the assets
+        #[allow(clippy::too_many_arguments)]
+        fn show_failure(
+            mut commands: Commands,
+            mut failures: EventReader<Failed>,
+            asset_server: Res<AssetServer>,
+            store: Res<AssetStore>,
+            config: Res<Config>,
+            locale: Res<Locale>,
+            mut windows: ResMut<Windows>,
+            entities: Query<(Entity, &UiEntity)>,
+        ) {
+            let paths: Vec<_> = failures.iter().map(|failed| failed.0.as_str()).collect();
+            if paths.is_empty() {
+                return;
+            }
+
+            let message = locale.format(Message::FailedToLoad, &[&paths.join(", ")]);
+
+            // also without the font
+            if let Some(window) = windows.get_primary_mut() {
+                window.set_title(format!("karpas - {}", message));
+            }
+
+            // in any other weight if the one of the label failed too
+            let role = config.theme.label;
+            let loaded =
+                |key: &FontKey| asset_server.get_load_state(&store.fonts[key]) == LoadState::Loaded;
+            let Some(key) = iter::once(role.font).chain(FontKey::ALL).find(loaded) else {
+                return;
+            };
+            let font = store.font(key);
+
+            for (entity, _) in entities.iter() {
+                commands.entity(entity).despawn();
+            }
+
+            commands
+                .spawn()
+                .insert(UiEntity)
+                .insert_bundle(UiCameraBundle::default());
 
-            loaded.send(Loaded);
+            commands
+                .spawn()
+                .insert(UiEntity)
+                .insert_bundle(NodeBundle {
+                    style: Style {
+                        size: Size {
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(100.0),
+                        },
+                        flex_direction: FlexDirection::ColumnReverse,
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    color: UiColor(Color::NONE),
+                    ..default()
+                })
+                .with_children(|cb| {
+                    cb.spawn().insert(UiEntity).insert_bundle(TextBundle {
+                        text: Text::with_section(
+                            message,
+                            TextStyle {
+                                font,
+                                font_size: role.size,
+                                color: Color::SALMON,
+                            },
+                            TextAlignment {
+                                vertical: VerticalAlign::Center,
+                                horizontal: HorizontalAlign::Center,
+                            },
+                        ),
+                        ..default()
+                    });
+                });
         }
 
-        fn detect_loaded(loaded: EventReader<Loaded>, mut stage: ResMut<State<Stage>>) {
-            if !loaded.is_empty() {
-                stage.set(Stage::Title).unwrap();
+        fn despawn_ui(mut commands: Commands, entities: Query<(Entity, &UiEntity)>) {
+            for (entity, _) in entities.iter() {
+                commands.entity(entity).despawn();
             }
         }
     }
@@ -218,104 +1620,67 @@ mod stag {
         use bevy::app::Plugin as PluginTrait;
         use bevy::prelude::*;
 
+        use crate::menu::{self, Menu, MenuEvent};
         use crate::Stage::Title as SelfStage;
-        use crate::{AssetStore, Config, Stage};
+        use crate::{clear_input, AssetStore, Config, Locale, Message, Stage, TextRole};
 
         pub struct Plugin;
         impl PluginTrait for Plugin {
             fn name(&self) -> &str { "title" }
 
             fn build(&self, app: &mut App) {
-                app.add_event::<CursorInput>();
-                app.add_event::<CursorSubmit>();
-                app.insert_resource(CursorState::Start);
+                app.add_plugin(menu::Plugin::<Action>::new(SelfStage));
 
-                app.add_system_set(SystemSet::on_enter(SelfStage).with_system(spawn_ui));
                 app.add_system_set(
-                    SystemSet::on_update(SelfStage)
-                        .with_system(cursor_input)
-                        .with_system(cursor_handle)
-                        .with_system(detect_move)
-                        .with_system(update_ui),
+                    SystemSet::on_enter(SelfStage)
+                        .with_system(spawn_ui)
+                        .with_system(clear_input),
                 );
+                app.add_system_set(SystemSet::on_update(SelfStage).with_system(handle_menu));
                 app.add_system_set(SystemSet::on_exit(SelfStage).with_system(despawn_ui));
-            }
-        }
 
-        enum CursorInput {
-            Up,
-            Down,
-            Submit,
+                // stages pushed over the title have their own ui
+                app.add_system_set(SystemSet::on_pause(SelfStage).with_system(despawn_ui));
+                app.add_system_set(
+                    SystemSet::on_resume(SelfStage)
+                        .with_system(spawn_ui)
+                        .with_system(clear_input),
+                );
+            }
         }
 
         #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-        enum CursorState {
+        enum Action {
             Start,
             Settings,
             Infos,
             Exit,
         }
-        impl CursorState {
-            fn next(&self) -> Self {
-                match *self {
-                    Self::Start => Self::Settings,
-                    Self::Settings => Self::Infos,
-                    Self::Infos => Self::Exit,
-                    Self::Exit => Self::Exit,
-                }
-            }
-
-            fn prev(&self) -> Self {
-                match *self {
-                    Self::Start => Self::Start,
-                    Self::Settings => Self::Start,
-                    Self::Infos => Self::Settings,
-                    Self::Exit => Self::Infos,
-                }
-            }
+        impl Action {
+            const ALL: [Self; 4] = [Self::Start, Self::Settings, Self::Infos, Self::Exit];
 
-            fn as_str(&self) -> &str {
+            fn message(&self) -> Message {
                 match *self {
-                    Self::Start => "Start",
-                    Self::Settings => "Settings",
-                    Self::Infos => "Infos",
-                    Self::Exit => "Exit",
-                }
-            }
-        }
-
-        enum CursorSubmit {
-            Start,
-            Settings,
-            Infos,
-            Exit,
-        }
-        impl From<CursorState> for CursorSubmit {
-            fn from(from: CursorState) -> Self {
-                match from {
-                    CursorState::Start => Self::Start,
-                    CursorState::Settings => Self::Settings,
-                    CursorState::Infos => Self::Infos,
-                    CursorState::Exit => Self::Exit,
+                    Self::Start => Message::Start,
+                    Self::Settings => Message::Settings,
+                    Self::Infos => Message::Infos,
+                    Self::Exit => Message::Exit,
                 }
             }
         }
 
-        fn spawn_ui(mut commands: Commands, assets: Res<AssetStore>) {
+        fn spawn_ui(
+            mut commands: Commands,
+            assets: Res<AssetStore>,
+            config: Res<Config>,
+            locale: Res<Locale>,
+        ) {
             commands
                 .spawn()
                 .insert(UiEntity)
                 .insert_bundle(UiCameraBundle::default());
 
-            let font = assets
-                .store
-                .get("font-zen")
-                .as_ref()
-                .unwrap()
-                .clone_weak()
-                .typed();
-
-            commands
+            let root = commands
                 .spawn()
                 .insert(UiEntity)
                 .insert_bundle(NodeBundle {
@@ -332,90 +1697,37 @@ mod stag {
                     color: UiColor(Color::NONE),
                     ..default()
                 })
-                .with_children(|cb| {
-                    for text in ["Start", "Settings", "Infos", "Exit"] {
-                        cb.spawn().insert(UiEntity).insert_bundle(TextBundle {
-                            text: Text::with_section(
-                                text,
-                                TextStyle {
-                                    font: font.clone_weak(),
-                                    font_size: 64.0,
-                                    color: Color::NONE,
-                                },
-                                TextAlignment {
-                                    vertical: VerticalAlign::Center,
-                                    horizontal: HorizontalAlign::Center,
-                                },
-                            ),
-                            ..default()
-                        });
-                    }
-                });
+                .id();
+
+            let items = Action::ALL
+                .into_iter()
+                .map(|action| {
+                    menu::Item::new(locale.get(action.message()), menu::Action::Submit(action))
+                })
+                .collect();
+            let menu = Menu {
+                items,
+                wrap: true,
+                item: config.theme.title,
+                selected: TextRole::new(config.theme.selected.font, config.theme.title.size),
+            };
+            menu::spawn(&mut commands, root, menu, &assets);
         }
 
         #[derive(Component)]
         struct UiEntity;
 
-        fn cursor_input(
-            key: Res<Input<KeyCode>>,
-            mut inputs: EventWriter<CursorInput>,
-            config: Res<Config>,
-        ) {
-            let config = &config.key.title;
-
-            if key.just_pressed(config.up) {
-                inputs.send(CursorInput::Up);
-            } else if key.just_pressed(config.down) {
-                inputs.send(CursorInput::Down);
-            } else if key.just_pressed(config.submit) {
-                inputs.send(CursorInput::Submit);
-            }
-        }
-
-        fn cursor_handle(
-            mut inputs: EventReader<CursorInput>,
-            mut state: ResMut<CursorState>,
-            mut moves: EventWriter<CursorSubmit>,
+        fn handle_menu(
+            mut events: EventReader<MenuEvent<Action>>,
+            mut stage: ResMut<State<Stage>>,
         ) {
-            if let Some(input) = inputs.iter().next() {
-                match *input {
-                    CursorInput::Up => {
-                        *state = state.prev();
-                    },
-                    CursorInput::Down => {
-                        *state = state.next();
-                    },
-                    CursorInput::Submit => {
-                        moves.send((*state).into());
-                    },
-                }
-            }
-        }
-
-        fn detect_move(mut moves: EventReader<CursorSubmit>, mut stage: ResMut<State<Stage>>) {
-            match moves.iter().next() {
-                Some(CursorSubmit::Start) => stage.set(Stage::Game).unwrap(),
-                Some(CursorSubmit::Settings) => stage.push(Stage::Settings).unwrap(),
-                Some(CursorSubmit::Infos) => stage.push(Stage::Infos).unwrap(),
-                Some(CursorSubmit::Exit) => stage.set(Stage::End).unwrap(),
-                None => (),
-            }
-        }
-
-        fn update_ui(state: Res<CursorState>, mut entities: Query<(&UiEntity, &mut Text)>) {
-            if !state.is_changed() {
-                return;
-            }
-
-            let state = state.as_str();
-            for (_, mut text) in entities.iter_mut() {
-                for section in text.sections.iter_mut() {
-                    if section.value == state {
-                        section.style.color = Color::SALMON;
-                    } else {
-                        section.style.color = Color::DARK_GRAY;
-                    }
-                }
+            match events.iter().next() {
+                Some(MenuEvent::Submit(Action::Start)) => stage.set(Stage::Game).unwrap(),
+                Some(MenuEvent::Submit(Action::Settings)) => stage.push(Stage::Settings).unwrap(),
+                Some(MenuEvent::Submit(Action::Infos)) => stage.push(Stage::Infos).unwrap(),
+                Some(MenuEvent::Submit(Action::Exit)) => stage.set(Stage::End).unwrap(),
+                // nowhere to go back from the title
+                Some(MenuEvent::Back) | None => (),
             }
         }
 
@@ -426,58 +1738,211 @@ mod stag {
         }
     }
 
-    pub mod game {
+    pub mod settings {
+        use std::fmt;
+
         use bevy::app::Plugin as PluginTrait;
-        use bevy::core::Stopwatch;
         use bevy::prelude::*;
 
-        use crate::AssetStore;
-        use crate::Stage::Game as SelfStage;
+        use crate::menu::{self, ItemEntity, Menu, MenuEntity, MenuEvent};
+        use crate::Stage::Settings as SelfStage;
+        use crate::{
+            clear_input, AssetStore, Binding, Config, ConfigFile, Controls, KeyConfig, Locale,
+            Message, PadButton, PadInput, Stage, TextRole,
+        };
 
         pub struct Plugin;
         impl PluginTrait for Plugin {
-            fn name(&self) -> &str { "game" }
+            fn name(&self) -> &str { "settings" }
 
             fn build(&self, app: &mut App) {
-                app.add_event::<FallingInput>();
-                app.add_event::<Landing>();
+                app.add_plugin(menu::Plugin::<Item>::new(SelfStage));
+                app.add_event::<CursorInput>();
+                app.insert_resource(Cursor::default());
 
                 app.add_system_set(
                     SystemSet::on_enter(SelfStage)
                         .with_system(spawn_ui)
-                        .with_system(spawn_area),
+                        .with_system(clear_input),
                 );
                 app.add_system_set(
                     SystemSet::on_update(SelfStage)
-                        .with_system(update_ui)
-                        .with_system(tick_falling)
-                        .with_system(falling_input)
-                        .with_system(falling_handle)
-                        .with_system(handle_landing),
+                        // the key or button captured for a binding must not move the cursor
+                        .with_system(capture_input.before(menu::Navigation))
+                        .with_system(cursor_input.label(Phase::Input).after(menu::Navigation))
+                        .with_system(cursor_handle.after(Phase::Input))
+                        .with_system(update_ui),
                 );
                 app.add_system_set(
                     SystemSet::on_exit(SelfStage)
                         .with_system(despawn_ui)
-                        .with_system(despawn_area),
+                        .with_system(save_config),
                 );
             }
         }
 
-        fn spawn_ui(mut commands: Commands, assets: Res<AssetStore>) {
-            let font = assets
-                .store
-                .get("font-zen")
-                .as_ref()
-                .unwrap()
-                .clone_weak()
-                .typed();
+        // read after the menu, not to edit in the frame the item is submitted
+        #[derive(Clone, Debug, PartialEq, Eq, Hash, SystemLabel)]
+        enum Phase {
+            Input,
+        }
+
+        #[derive(Clone, Copy)]
+        enum CursorInput {
+            Up,
+            Down,
+            Submit,
+            Back,
+        }
+
+        #[derive(Clone, Copy, PartialEq)]
+        enum Item {
+            Key(Binding),
+            Value(Value),
+        }
+        impl Item {
+            const ALL: [Self; 18] = [
+                Self::Key(Binding::TitleUp),
+                Self::Key(Binding::TitleDown),
+                Self::Key(Binding::TitleSubmit),
+                Self::Key(Binding::TitleBack),
+                Self::Key(Binding::Left),
+                Self::Key(Binding::Right),
+                Self::Key(Binding::SoftDrop),
+                Self::Key(Binding::HardDrop),
+                Self::Key(Binding::P90Spin),
+                Self::Key(Binding::N90Spin),
+                Self::Key(Binding::P180Spin),
+                Self::Key(Binding::Hold),
+                Self::Key(Binding::Pause),
+                Self::Value(Value::Das),
+                Self::Value(Value::Arr),
+                Self::Value(Value::DasCut),
+                Self::Value(Value::SoftDropDas),
+                Self::Value(Value::Previews),
+            ];
+
+            fn text(&self, config: &Config, locale: &Locale, pad: Option<Gamepad>) -> String {
+                match *self {
+                    Self::Key(binding) => binding.text(config, locale, pad),
+                    Self::Value(value) =>
+                        format!("{}  {}", locale.get(value.message()), value.get(config)),
+                }
+            }
+        }
+
+        #[derive(Clone, Copy, PartialEq)]
+        enum Value {
+            Das,
+            Arr,
+            DasCut,
+            SoftDropDas,
+            Previews,
+        }
+        impl Value {
+            fn message(&self) -> Message {
+                match *self {
+                    Self::Das => Message::Das,
+                    Self::Arr => Message::Arr,
+                    Self::DasCut => Message::DasCut,
+                    Self::SoftDropDas => Message::SoftDropDas,
+                    Self::Previews => Message::Previews,
+                }
+            }
+
+            /// (step, max)
+            fn range(&self) -> (u64, u64) {
+                match *self {
+                    Self::Das | Self::DasCut | Self::SoftDropDas => (10, 500),
+                    Self::Arr => (1, 200),
+                    Self::Previews => (1, 6),
+                }
+            }
+
+            fn get(&self, config: &Config) -> u64 {
+                match *self {
+                    Self::Das => config.handling.das,
+                    Self::Arr => config.handling.arr,
+                    Self::DasCut => config.handling.das_cut,
+                    Self::SoftDropDas => config.handling.soft_drop_das,
+                    Self::Previews => config.rule.previews as u64,
+                }
+            }
+
+            fn set(&self, config: &mut Config, value: u64) {
+                match *self {
+                    Self::Das => config.handling.das = value,
+                    Self::Arr => config.handling.arr = value,
+                    Self::DasCut => config.handling.das_cut = value,
+                    Self::SoftDropDas => config.handling.soft_drop_das = value,
+                    Self::Previews => config.rule.previews = value as usize,
+                }
+            }
+
+            fn add(&self, config: &mut Config, up: bool) {
+                let (step, max) = self.range();
+                let value = self.get(config);
+
+                let value = match up {
+                    true => (value + step).min(max),
+                    false => value.saturating_sub(step),
+                };
+                self.set(config, value);
+            }
+        }
+
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mode {
+            Select,
+            /// waiting for the key to bind
+            Capture,
+            /// up and down change the value
+            Edit,
+        }
+
+        struct Cursor {
+            /// the one taking the input in the capture and edit modes
+            item: Option<Item>,
+            mode: Mode,
+            message: String,
+            /// whose profile is shown and edited, the one used last
+            pad: Option<Gamepad>,
+        }
+        impl Default for Cursor {
+            fn default() -> Self {
+                Self {
+                    item: None,
+                    mode: Mode::Select,
+                    message: String::new(),
+                    pad: None,
+                }
+            }
+        }
+        fn spawn_ui(
+            mut commands: Commands,
+            assets: Res<AssetStore>,
+            config: Res<Config>,
+            locale: Res<Locale>,
+            gamepads: Res<Gamepads>,
+            mut cursor: ResMut<Cursor>,
+        ) {
+            *cursor = Cursor {
+                pad: gamepads.iter().min_by_key(|gamepad| gamepad.0).copied(),
+                ..default()
+            };
 
             commands
                 .spawn()
                 .insert(UiEntity)
                 .insert_bundle(UiCameraBundle::default());
 
-            commands
+            let theme = &config.theme;
+            let alignment = TextAlignment {
+                vertical: VerticalAlign::Center,
+                horizontal: HorizontalAlign::Center,
+            };
+
+            let root = commands
                 .spawn()
                 .insert(UiEntity)
                 .insert_bundle(NodeBundle {
@@ -488,38 +1953,656 @@ mod stag {
                         },
                         flex_direction: FlexDirection::ColumnReverse,
                         align_items: AlignItems::Center,
-                        justify_content: JustifyContent::FlexStart,
+                        justify_content: JustifyContent::Center,
                         ..default()
                     },
                     color: UiColor(Color::NONE),
                     ..default()
                 })
                 .with_children(|cb| {
-                    cb.spawn()
-                        .insert(UiEntity)
-                        .insert(ScoreEntity)
-                        .insert(Score(0))
-                        .insert_bundle(TextBundle {
-                            text: Text::with_section(
-                                "",
-                                TextStyle {
-                                    font,
-                                    font_size: 48.0,
-                                    color: Color::ANTIQUE_WHITE,
-                                },
-                                TextAlignment {
-                                    vertical: VerticalAlign::Center,
-                                    horizontal: HorizontalAlign::Center,
-                                },
-                            ),
+                    cb.spawn().insert(UiEntity).insert_bundle(TextBundle {
+                        text: Text::with_section(
+                            locale.get(Message::Settings),
+                            theme.heading.style(&assets, Color::WHITE),
+                            alignment,
+                        ),
+                        style: Style {
+                            margin: Rect {
+                                bottom: Val::Px(16.0),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        ..default()
+                    });
+                })
+                .id();
+
+            // rows in the size of the small labels, the selected one in its own weight
+            let mut items: Vec<_> = Item::ALL
+                .into_iter()
+                .map(|item| {
+                    let label = item.text(&config, &locale, cursor.pad);
+                    menu::Item::new(label, menu::Action::Submit(item))
+                })
+                .collect();
+            items.push(menu::Item::new(
+                locale.get(Message::Back),
+                menu::Action::Back,
+            ));
+            let menu = Menu {
+                items,
+                wrap: false,
+                item: theme.small,
+                selected: TextRole::new(theme.selected.font, theme.small.size),
+            };
+            menu::spawn(&mut commands, root, menu, &assets);
+
+            commands.entity(root).with_children(|cb| {
+                cb.spawn()
+                    .insert(UiEntity)
+                    .insert(MessageEntity)
+                    .insert_bundle(TextBundle {
+                        text: Text::with_section(
+                            "",
+                            theme.small.style(&assets, Color::GRAY),
+                            alignment,
+                        ),
+                        style: Style {
+                            margin: Rect {
+                                top: Val::Px(16.0),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        ..default()
+                    });
+            });
+        }
+
+        #[derive(Component)]
+        struct UiEntity;
+
+        #[derive(Component)]
+        struct MessageEntity;
+
+        fn capture_input(
+            mut key: ResMut<Input<KeyCode>>,
+            mut pad: ResMut<Input<PadInput>>,
+            mut cursor: ResMut<Cursor>,
+            mut config: ResMut<Config>,
+            locale: Res<Locale>,
+            mut menus: Query<&mut MenuEntity>,
+        ) {
+            if let Some(PadInput(gamepad, _)) = pad.get_just_pressed().next().copied() {
+                if cursor.pad != Some(gamepad) {
+                    cursor.pad = Some(gamepad);
+                }
+            }
+
+            if cursor.mode != Mode::Capture {
+                return;
+            }
+
+            let pressed_key = key.get_just_pressed().next().copied();
+            let pressed_pad = pad.get_just_pressed().next().copied();
+            if pressed_key.is_none() && pressed_pad.is_none() {
+                return;
+            }
+            key.clear();
+            pad.clear();
+
+            let Some(Item::Key(binding)) = cursor.item else {
+                unreachable!();
+            };
+
+            cursor.mode = Mode::Select;
+            set_editing(&mut menus, false);
+            cursor.message = match (pressed_key, pressed_pad) {
+                (Some(KeyCode::Escape), _) | (None, Some(PadInput(_, PadButton::Select))) =>
+                    locale.get(Message::Cancelled),
+                (Some(KeyCode::Back), _) if binding.is_optional() => {
+                    binding.set(&mut config.key, None);
+                    locale.format(Message::Unbound, &[&locale.get(binding.message())])
+                },
+                (Some(pressed), _) => bind(binding, &mut config.key, pressed, &locale),
+                (None, Some(PadInput(gamepad, pressed))) =>
+                    bind(binding, config.pad.profile_mut(gamepad), pressed, &locale),
+                (None, None) => unreachable!(),
+            };
+        }
+
+        /// swaps with the conflicting binding, if it can take the old key
+        fn bind<B>(
+            binding: Binding,
+            config: &mut KeyConfig<B>,
+            pressed: B,
+            locale: &Locale,
+        ) -> String
+        where
+            B: Copy + PartialEq + fmt::Debug,
+        {
+            let old = binding.get(config);
+
+            match binding.conflict(config, pressed) {
+                Some(other) if old.is_none() && !other.is_optional() => locale.format(
+                    Message::AlreadyBound,
+                    &[&format!("{:?}", pressed), &locale.get(other.message())],
+                ),
+                Some(other) => {
+                    other.set(config, old);
+                    binding.set(config, Some(pressed));
+                    locale.format(Message::Swapped, &[&locale.get(other.message())])
+                },
+                None => {
+                    binding.set(config, Some(pressed));
+                    String::new()
+                },
+            }
+        }
+
+        fn cursor_input(controls: Controls, mut inputs: EventWriter<CursorInput>) {
+            let pressed = controls.just_pressed(&[
+                (Binding::TitleUp, CursorInput::Up),
+                (Binding::TitleDown, CursorInput::Down),
+                (Binding::TitleSubmit, CursorInput::Submit),
+                (Binding::TitleBack, CursorInput::Back),
+            ]);
+            for input in pressed {
+                inputs.send(input);
+            }
+        }
+
+        fn cursor_handle(
+            mut inputs: EventReader<CursorInput>,
+            mut events: EventReader<MenuEvent<Item>>,
+            mut cursor: ResMut<Cursor>,
+            mut config: ResMut<Config>,
+            locale: Res<Locale>,
+            mut menus: Query<&mut MenuEntity>,
+            mut stage: ResMut<State<Stage>>,
+        ) {
+            // read in every mode, not to be left over for the next edit
+            let inputs: Vec<_> = inputs.iter().copied().collect();
+            if let (Mode::Edit, Some(Item::Value(value))) = (cursor.mode, cursor.item) {
+                for input in inputs {
+                    match input {
+                        CursorInput::Up => value.add(&mut config, true),
+                        CursorInput::Down => value.add(&mut config, false),
+                        CursorInput::Submit | CursorInput::Back => {
+                            cursor.mode = Mode::Select;
+                            cursor.message.clear();
+                            set_editing(&mut menus, false);
+                            break;
+                        },
+                    }
+                }
+            }
+
+            for event in events.iter() {
+                match *event {
+                    MenuEvent::Submit(item @ Item::Key(binding)) => {
+                        cursor.item = Some(item);
+                        cursor.mode = Mode::Capture;
+                        cursor.message = locale.get(match binding.is_optional() {
+                            true => Message::CaptureOptionalKey,
+                            false => Message::CaptureKey,
+                        });
+                        set_editing(&mut menus, true);
+                    },
+                    MenuEvent::Submit(item @ Item::Value(_)) => {
+                        cursor.item = Some(item);
+                        cursor.mode = Mode::Edit;
+                        cursor.message = locale.get(Message::EditValue);
+                        set_editing(&mut menus, true);
+                    },
+                    MenuEvent::Back => {
+                        stage.pop().unwrap();
+                        break;
+                    },
+                }
+            }
+        }
+
+        fn set_editing(menus: &mut Query<&mut MenuEntity>, editing: bool) {
+            for mut menu in menus.iter_mut() {
+                menu.editing = editing;
+            }
+        }
+
+        fn update_ui(
+            cursor: Res<Cursor>,
+            config: Res<Config>,
+            locale: Res<Locale>,
+            mut items: Query<(&ItemEntity<Item>, &mut Text), Without<MessageEntity>>,
+            mut messages: Query<(&MessageEntity, &mut Text), Without<ItemEntity<Item>>>,
+        ) {
+            // the menu styles the rows, only the values change here
+            if !cursor.is_changed() && !config.is_changed() {
+                return;
+            }
+
+            for (entity, mut text) in items.iter_mut() {
+                if let Some(item) = entity.action() {
+                    text.sections[0].value = item.text(&config, &locale, cursor.pad);
+                }
+            }
+
+            for (_, mut text) in messages.iter_mut() {
+                text.sections[0].value = cursor.message.clone();
+            }
+        }
+
+        fn despawn_ui(mut commands: Commands, entities: Query<(Entity, &UiEntity)>) {
+            for (entity, _) in entities.iter() {
+                commands.entity(entity).despawn();
+            }
+        }
+
+        fn save_config(config: Res<Config>, file: Res<ConfigFile>) {
+            match file.save(&config) {
+                Ok(()) => info!("saved the config"),
+                Err(e) => error!("{}", e),
+            }
+        }
+    }
+
+    pub mod infos {
+        use bevy::app::Plugin as PluginTrait;
+        use bevy::prelude::*;
+
+        use crate::Stage::Infos as SelfStage;
+        use crate::{clear_input, AssetStore, Binding, Config, Controls, Locale, Message, Stage};
+
+        pub struct Plugin;
+        impl PluginTrait for Plugin {
+            fn name(&self) -> &str { "infos" }
+
+            fn build(&self, app: &mut App) {
+                app.add_system_set(
+                    SystemSet::on_enter(SelfStage)
+                        .with_system(spawn_ui)
+                        .with_system(clear_input),
+                );
+                app.add_system_set(
+                    SystemSet::on_update(SelfStage)
+                        .with_system(scroll)
+                        .with_system(handle_back),
+                );
+                app.add_system_set(SystemSet::on_exit(SelfStage).with_system(despawn_ui));
+            }
+        }
+
+        const LICENSE: &str = include_str!("../assets/fonts/zkgn/OFL.txt");
+
+        // pixels per second while the key is held
+        const SCROLL_SPEED: f32 = 600.0;
+
+        fn spawn_ui(
+            mut commands: Commands,
+            assets: Res<AssetStore>,
+            config: Res<Config>,
+            locale: Res<Locale>,
+            gamepads: Res<Gamepads>,
+        ) {
+            commands
+                .spawn()
+                .insert(UiEntity)
+                .insert_bundle(UiCameraBundle::default());
+
+            let theme = &config.theme;
+            let alignment = TextAlignment {
+                vertical: VerticalAlign::Center,
+                horizontal: HorizontalAlign::Center,
+            };
+            let margin = |top, bottom| Style {
+                margin: Rect {
+                    top: Val::Px(top),
+                    bottom: Val::Px(bottom),
+                    ..default()
+                },
+                ..default()
+            };
+
+            let mut lines = vec![
+                (
+                    locale.get(Message::Infos),
+                    theme.heading,
+                    Color::WHITE,
+                    margin(16.0, 16.0),
+                ),
+                (
+                    locale.format(Message::Version, &[&env!("CARGO_PKG_VERSION")]),
+                    theme.small,
+                    Color::GRAY,
+                    margin(0.0, 32.0),
+                ),
+                (
+                    locale.get(Message::Controls),
+                    theme.label,
+                    Color::WHITE,
+                    margin(0.0, 8.0),
+                ),
+            ];
+
+            let pad = gamepads.iter().min_by_key(|gamepad| gamepad.0).copied();
+            for binding in Binding::ALL {
+                lines.push((
+                    binding.text(&config, &locale, pad),
+                    theme.small,
+                    Color::GRAY,
+                    default(),
+                ));
+            }
+
+            lines.push((
+                locale.get(Message::Credits),
+                theme.label,
+                Color::WHITE,
+                margin(32.0, 8.0),
+            ));
+            lines.push((
+                "Zen Kaku Gothic New".to_string(),
+                theme.label,
+                Color::GRAY,
+                margin(0.0, 8.0),
+            ));
+            for line in LICENSE.lines() {
+                lines.push((line.to_string(), theme.small, Color::DARK_GRAY, default()));
+            }
+
+            commands
+                .spawn()
+                .insert(UiEntity)
+                .insert(ViewEntity)
+                .insert_bundle(NodeBundle {
+                    style: Style {
+                        size: Size {
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(100.0),
+                        },
+                        flex_direction: FlexDirection::ColumnReverse,
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::FlexStart,
+                        overflow: Overflow::Hidden,
+                        ..default()
+                    },
+                    color: UiColor(Color::NONE),
+                    ..default()
+                })
+                .with_children(|cb| {
+                    cb.spawn()
+                        .insert(UiEntity)
+                        .insert(ContentEntity)
+                        .insert_bundle(NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::ColumnReverse,
+                                align_items: AlignItems::Center,
+                                flex_shrink: 0.0,
+                                ..default()
+                            },
+                            color: UiColor(Color::NONE),
+                            ..default()
+                        })
+                        .with_children(|cb| {
+                            for (line, role, color, text_style) in lines {
+                                cb.spawn().insert(UiEntity).insert_bundle(TextBundle {
+                                    text: Text::with_section(
+                                        line,
+                                        role.style(&assets, color),
+                                        alignment,
+                                    ),
+                                    style: text_style,
+                                    ..default()
+                                });
+                            }
+                        });
+                });
+        }
+
+        #[derive(Component)]
+        struct UiEntity;
+
+        #[derive(Component)]
+        struct ViewEntity;
+
+        #[derive(Component)]
+        struct ContentEntity;
+
+        fn scroll(
+            controls: Controls,
+            time: Res<Time>,
+            views: Query<&Node, With<ViewEntity>>,
+            mut contents: Query<(&Node, &mut Style), With<ContentEntity>>,
+        ) {
+            let up = controls.pressed(Binding::TitleUp);
+            let down = controls.pressed(Binding::TitleDown);
+
+            let direction = match (up, down) {
+                (true, false) => -1.0,
+                (false, true) => 1.0,
+                _ => return,
+            };
+
+            let Ok(view) = views.get_single() else {
+                return;
+            };
+
+            for (content, mut style) in contents.iter_mut() {
+                let max = (content.size.y - view.size.y).max(0.0);
+                let top = match style.position.top {
+                    Val::Px(top) => top,
+                    _ => 0.0,
+                };
+
+                // the ui is laid out bottom up, so a larger top shows the lines below
+                let top = (top + direction * SCROLL_SPEED * time.delta_seconds()).clamp(0.0, max);
+                style.position.top = Val::Px(top);
+            }
+        }
+
+        fn handle_back(controls: Controls, mut stage: ResMut<State<Stage>>) {
+            if controls.any_just_pressed([Binding::TitleBack, Binding::TitleSubmit]) {
+                stage.pop().unwrap();
+            }
+        }
+
+        fn despawn_ui(mut commands: Commands, entities: Query<(Entity, &UiEntity)>) {
+            for (entity, _) in entities.iter() {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+
+    pub mod game {
+        use std::collections::VecDeque;
+        use std::path::Path;
+        use std::time::Duration;
+
+        use bevy::app::Plugin as PluginTrait;
+        use bevy::core::Stopwatch;
+        use bevy::ecs::schedule::StateError;
+        use bevy::prelude::*;
+        use serde::{Deserialize, Serialize};
+
+        use crate::logic::board::Board;
+        use crate::logic::level::{Gravity, Progress, SoftDrop};
+        use crate::logic::lock::Lock;
+        use crate::logic::piece::{Kind, Piece};
+        use crate::logic::randomizer::{Randomizer, RandomizerKind};
+        use crate::logic::repeat::Repeat;
+        use crate::logic::score::{ScoreTable, Scoring};
+        use crate::logic::srs;
+        use crate::Stage::Game as SelfStage;
+        use crate::{
+            clear_input, read_ron, write_ron, AssetStore, Binding, Config, Controls, FileError,
+            Launch, Locale, Message, RuleConfig, Stage,
+        };
+
+        pub struct Plugin;
+        impl PluginTrait for Plugin {
+            fn name(&self) -> &str { "game" }
+
+            fn build(&self, app: &mut App) {
+                app.add_event::<FallingInput>();
+                app.add_event::<Landing>();
+                app.add_event::<LinesCleared>();
+                app.add_event::<Locked>();
+                app.add_event::<Dropped>();
+                app.add_event::<TopOut>();
+                app.insert_resource(new_board());
+                app.insert_resource(Scoring::new(ScoreTable::default()));
+                app.insert_resource(Progress::new(1));
+                app.insert_resource(Stats::default());
+                app.insert_resource(Record::default());
+                app.insert_resource(Queue::new(RandomizerKind::Bag7, 0, 0));
+                app.insert_resource(Hold::default());
+                app.insert_resource(Repeats::default());
+                app.insert_resource(Fall::default());
+                app.insert_resource(Clock::default());
+                app.insert_resource(Playback::default());
+                app.insert_resource(Recording::default());
+
+                app.add_system_set(
+                    SystemSet::on_enter(SelfStage)
+                        .with_system(reset_progress)
+                        .with_system(spawn_ui)
+                        .with_system(spawn_area),
+                );
+                app.add_system_set(
+                    SystemSet::on_update(SelfStage)
+                        .with_system(update_ui)
+                        .with_system(update_level_ui)
+                        .with_system(tick_clock.label(Phase::Clock))
+                        .with_system(tick_stats.after(Phase::Clock))
+                        .with_system(tick_falling.label(Phase::Gravity).after(Phase::Fall))
+                        .with_system(falling_input.label(Phase::Input).after(Phase::Clock))
+                        .with_system(replay_inputs.label(Phase::Input).after(Phase::Clock))
+                        .with_system(record_inputs.after(Phase::Input))
+                        .with_system(falling_handle.label(Phase::Fall).after(Phase::Hold))
+                        .with_system(tick_lock.label(Phase::Lock).after(Phase::Gravity))
+                        .with_system(handle_landing.label(Phase::Land).after(Phase::Lock))
+                        .with_system(log_lines_cleared)
+                        .with_system(update_progress.label(Phase::Progress).after(Phase::Land))
+                        .with_system(handle_top_out.label(Phase::TopOut).after(Phase::Progress))
+                        .with_system(render_falling)
+                        .with_system(render_ghost)
+                        .with_system(render_board)
+                        .with_system(render_previews)
+                        .with_system(handle_hold.label(Phase::Hold).after(Phase::Input))
+                        .with_system(render_hold)
+                        .with_system(pause_input.after(Phase::TopOut)),
+                );
+                app.add_system_set(
+                    SystemSet::on_exit(SelfStage)
+                        .with_system(despawn_ui)
+                        .with_system(despawn_area),
+                );
+
+                // the systems above stop while paused, which freezes the timers
+                app.add_system_set(SystemSet::on_pause(SelfStage).with_system(hide_area));
+                app.add_system_set(
+                    SystemSet::on_resume(SelfStage)
+                        .with_system(show_area)
+                        .with_system(clear_input),
+                );
+            }
+        }
+
+        // landings are handled in the frame they happen, not to lose the last lock
+        #[derive(Clone, Debug, PartialEq, Eq, Hash, SystemLabel)]
+        enum Phase {
+            Clock,
+            Input,
+            Hold,
+            Fall,
+            Gravity,
+            Lock,
+            Land,
+            Progress,
+            TopOut,
+        }
+
+        fn spawn_ui(mut commands: Commands, assets: Res<AssetStore>, config: Res<Config>) {
+            let theme = &config.theme;
+
+            commands
+                .spawn()
+                .insert(UiEntity)
+                .insert_bundle(UiCameraBundle::default());
+
+            commands
+                .spawn()
+                .insert(UiEntity)
+                .insert_bundle(NodeBundle {
+                    style: Style {
+                        size: Size {
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(100.0),
+                        },
+                        flex_direction: FlexDirection::ColumnReverse,
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::FlexStart,
+                        ..default()
+                    },
+                    color: UiColor(Color::NONE),
+                    ..default()
+                })
+                .with_children(|cb| {
+                    cb.spawn()
+                        .insert(UiEntity)
+                        .insert_bundle(NodeBundle {
                             style: Style {
+                                flex_direction: FlexDirection::Row,
+                                align_items: AlignItems::Center,
                                 margin: Rect {
                                     top: Val::Px(32.0),
                                     ..default()
                                 },
                                 ..default()
                             },
+                            color: UiColor(Color::NONE),
                             ..default()
+                        })
+                        .with_children(|cb| {
+                            cb.spawn()
+                                .insert(UiEntity)
+                                .insert(LevelEntity)
+                                .insert(Level(1))
+                                .insert_bundle(TextBundle {
+                                    text: Text::with_section(
+                                        "",
+                                        theme.label.style(&assets, Color::GRAY),
+                                        TextAlignment {
+                                            vertical: VerticalAlign::Center,
+                                            horizontal: HorizontalAlign::Center,
+                                        },
+                                    ),
+                                    style: Style {
+                                        margin: Rect {
+                                            right: Val::Px(32.0),
+                                            ..default()
+                                        },
+                                        ..default()
+                                    },
+                                    ..default()
+                                });
+
+                            cb.spawn()
+                                .insert(UiEntity)
+                                .insert(ScoreEntity)
+                                .insert(Score(0))
+                                .insert_bundle(TextBundle {
+                                    text: Text::with_section(
+                                        "",
+                                        theme.score.style(&assets, Color::ANTIQUE_WHITE),
+                                        TextAlignment {
+                                            vertical: VerticalAlign::Center,
+                                            horizontal: HorizontalAlign::Center,
+                                        },
+                                    ),
+                                    ..default()
+                                });
                         });
                 });
         }
@@ -530,479 +2613,3175 @@ mod stag {
         #[derive(Component)]
         struct Score(u32);
 
+        #[derive(Component)]
+        struct LevelEntity;
+
+        #[derive(Component)]
+        struct Level(u32);
+
+        #[derive(Default)]
+        struct Stats {
+            time: Stopwatch,
+            pieces: u32,
+        }
+
+        fn reset_progress(
+            config: Res<Config>,
+            mut scoring: ResMut<Scoring>,
+            mut progress: ResMut<Progress>,
+            mut stats: ResMut<Stats>,
+        ) {
+            *scoring = Scoring::new(config.rule.score.clone());
+            *progress = Progress::new(config.rule.lines_per_level);
+            *stats = Stats::default();
+        }
+
+        fn tick_stats(clock: Res<Clock>, mut stats: ResMut<Stats>) { stats.time.tick(clock.delta); }
+
+        struct Locked {
+            lines: usize,
+        }
+
+        struct Dropped {
+            rows: u32,
+            hard: bool,
+        }
+
+        fn update_progress(
+            mut scoring: ResMut<Scoring>,
+            mut progress: ResMut<Progress>,
+            mut stats: ResMut<Stats>,
+            mut drops: EventReader<Dropped>,
+            mut locks: EventReader<Locked>,
+            mut scores: Query<(&ScoreEntity, &mut Score)>,
+            mut levels: Query<(&LevelEntity, &mut Level)>,
+        ) {
+            for drop in drops.iter() {
+                scoring.drop(drop.rows, drop.hard);
+            }
+
+            // scored by the level before the clear
+            for lock in locks.iter() {
+                scoring.lock(lock.lines, progress.level());
+                progress.add_lines(lock.lines as u32);
+                stats.pieces += 1;
+            }
+
+            for (_, mut score) in scores.iter_mut() {
+                if score.0 != scoring.score() {
+                    score.0 = scoring.score();
+                }
+            }
+
+            for (_, mut level) in levels.iter_mut() {
+                if level.0 != progress.level() {
+                    level.0 = progress.level();
+                }
+            }
+        }
+
         fn update_ui(mut entities: Query<(&ScoreEntity, &mut Text, &Score), Changed<Score>>) {
             for (_, mut text, score) in entities.iter_mut() {
                 for section in text.sections.iter_mut() {
                     section.value = score.0.to_string();
                 }
             }
-        }
+        }
+
+        fn update_level_ui(
+            locale: Res<Locale>,
+            mut entities: Query<(&LevelEntity, &mut Text, &Level), Changed<Level>>,
+        ) {
+            for (_, mut text, level) in entities.iter_mut() {
+                for section in text.sections.iter_mut() {
+                    section.value = locale.format(Message::LevelCounter, &[&level.0]);
+                }
+            }
+        }
+
+        fn despawn_ui(mut commands: Commands, entities: Query<(Entity, &UiEntity)>) {
+            for (entity, _) in entities.iter() {
+                commands.entity(entity).despawn();
+            }
+        }
+
+        #[derive(Component)]
+        struct UiEntity;
+
+        const BLOCK_SIZE: f32 = 48.0;
+        const AREA_BLOCKS: (usize, usize) = (10, 16);
+        const AREA_SIZE: (f32, f32) = (
+            BLOCK_SIZE * AREA_BLOCKS.0 as f32,
+            BLOCK_SIZE * AREA_BLOCKS.1 as f32,
+        );
+
+        // rows above the visible area, where pieces spawn
+        const BUFFER_BLOCKS: usize = 8;
+        const SPAWN_POSITION: (i32, i32) = (4, 15);
+
+        #[allow(clippy::too_many_arguments)]
+        fn spawn_area(
+            mut commands: Commands,
+            config: Res<Config>,
+            mut board: ResMut<Board>,
+            mut queue: ResMut<Queue>,
+            mut hold: ResMut<Hold>,
+            mut repeats: ResMut<Repeats>,
+            mut fall: ResMut<Fall>,
+            launch: Res<Launch>,
+            mut playback: ResMut<Playback>,
+            mut recording: ResMut<Recording>,
+        ) {
+            commands
+                .spawn()
+                .insert(AreaEntity)
+                .insert_bundle(OrthographicCameraBundle::new_2d());
+
+            commands
+                .spawn()
+                .insert(AreaEntity)
+                .insert(AreaFieldEntity)
+                .insert_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::BLACK,
+                        custom_size: Some(Vec2::new(AREA_SIZE.0, AREA_SIZE.1)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(0.0, 0.0, 0.0),
+                    ..default()
+                });
+
+            *board = new_board();
+            let seed = match &launch.replay {
+                Some(replay) => replay.seed,
+                None => launch
+                    .seed
+                    .or(config.rule.seed)
+                    .unwrap_or_else(rand::random),
+            };
+
+            *queue = Queue::new(config.rule.randomizer, seed, config.rule.previews);
+            *hold = Hold::default();
+            *repeats = Repeats::default();
+            *fall = Fall::default();
+            *playback = Playback::default();
+            recording.0 = Replay {
+                seed,
+                rule: config.rule.clone(),
+                frames: Vec::new(),
+            };
+
+            spawn_falling(&mut commands, queue.next());
+        }
+
+        fn new_board() -> Board { Board::new(AREA_BLOCKS.0, AREA_BLOCKS.1 + BUFFER_BLOCKS) }
+
+        fn transform_as_in_area(x: f32, y: f32) -> (f32, f32) {
+            (
+                BLOCK_SIZE * (x + 0.5) - AREA_SIZE.0 / 2.0,
+                BLOCK_SIZE * (y + 0.5) - AREA_SIZE.1 / 2.0,
+            )
+        }
+
+        fn color_of(kind: Kind) -> Color {
+            match kind {
+                Kind::I => Color::AQUAMARINE,
+                Kind::J => Color::BLUE,
+                Kind::L => Color::ORANGE,
+                Kind::O => Color::YELLOW,
+                Kind::S => Color::GREEN,
+                Kind::T => Color::PINK,
+                Kind::Z => Color::RED,
+            }
+        }
+
+        #[derive(Component)]
+        struct AreaFieldEntity;
+
+        #[derive(Component)]
+        struct MinoEntity;
+
+        #[derive(Component)]
+        struct FallingEntity;
+
+        #[derive(Component)]
+        struct Falling(Piece);
+
+        const FRAMES_PER_SEC: f32 = 60.0;
+
+        /// rows the gravity has yet to move the falling piece by
+        #[derive(Default)]
+        struct Fall {
+            rows: f32,
+        }
+
+        fn tick_falling(
+            mut fall: ResMut<Fall>,
+            clock: Res<Clock>,
+            config: Res<Config>,
+            progress: Res<Progress>,
+            board: Res<Board>,
+            mut entities: Query<(&FallingEntity, &mut Falling)>,
+        ) {
+            // no faster than the instant gravity, even for tiny frames per row
+            let instant = board.height() as f32;
+            fall.rows += match config.rule.gravity.get(progress.level()) {
+                Gravity::FramesPerRow(frames) =>
+                    (clock.delta.as_secs_f32() * FRAMES_PER_SEC / frames).min(instant),
+                Gravity::Instant => instant,
+            };
+
+            if fall.rows < 1.0 {
+                return;
+            }
+
+            for (_, mut falling) in entities.iter_mut() {
+                let mut moved = falling.0;
+                while fall.rows >= 1.0 && board.fits(&moved.shifted(0, -1)) {
+                    moved = moved.shifted(0, -1);
+                    fall.rows -= 1.0;
+                }
+
+                if moved != falling.0 {
+                    falling.0 = moved;
+                }
+            }
+
+            fall.rows = fall.rows.fract();
+        }
+
+        #[derive(Component)]
+        struct Locking(Lock);
+
+        fn tick_lock(
+            clock: Res<Clock>,
+            config: Res<Config>,
+            board: Res<Board>,
+            mut entities: Query<(&Falling, &mut Locking)>,
+            mut landings: EventWriter<Landing>,
+        ) {
+            for (falling, mut locking) in entities.iter_mut() {
+                let grounded = !board.fits(&falling.0.shifted(0, -1));
+
+                if locking
+                    .0
+                    .tick(falling.0.y, grounded, clock.delta, &config.rule.lock_delay)
+                {
+                    landings.send(Landing);
+                }
+            }
+        }
+
+        struct Landing;
+
+        #[allow(clippy::too_many_arguments)]
+        fn handle_landing(
+            mut commands: Commands,
+            mut landings: EventReader<Landing>,
+            mut board: ResMut<Board>,
+            entities: Query<(Entity, &FallingEntity, &Falling)>,
+            mut clears: EventWriter<LinesCleared>,
+            mut locks: EventWriter<Locked>,
+            mut top_outs: EventWriter<TopOut>,
+            mut queue: ResMut<Queue>,
+            mut hold: ResMut<Hold>,
+        ) {
+            if landings.iter().count() == 0 {
+                return;
+            }
+
+            for (entity, _, falling) in entities.iter() {
+                // may have been moved off the ground after landing
+                if board.fits(&falling.0.shifted(0, -1)) {
+                    continue;
+                }
+
+                board.place(&falling.0);
+                commands.entity(entity).despawn_recursive();
+                hold.used = false;
+
+                let rows = board.clear_lines();
+                locks.send(Locked { lines: rows.len() });
+
+                if !rows.is_empty() {
+                    clears.send(LinesCleared {
+                        count: rows.len(),
+                        rows,
+                    });
+                }
+
+                // lock out: locked completely above the visible area
+                let locked_out = falling
+                    .0
+                    .cells()
+                    .into_iter()
+                    .all(|(_, y)| y >= AREA_BLOCKS.1 as i32);
+
+                // block out: the next piece overlaps the stack
+                let next = queue.next();
+                if locked_out || !board.fits(&next) {
+                    top_outs.send(TopOut);
+                    continue;
+                }
+
+                spawn_falling(&mut commands, next);
+            }
+        }
+
+        struct TopOut;
+
+        /// results of the last game
+        #[derive(Default)]
+        pub struct Record {
+            pub score: u32,
+            pub lines: u32,
+            pub level: u32,
+            pub time: Duration,
+            pub pieces: u32,
+            pub seed: u64,
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        fn handle_top_out(
+            mut top_outs: EventReader<TopOut>,
+            scoring: Res<Scoring>,
+            progress: Res<Progress>,
+            stats: Res<Stats>,
+            queue: Res<Queue>,
+            mut record: ResMut<Record>,
+            mut stage: ResMut<State<Stage>>,
+            launch: Res<Launch>,
+            recording: Res<Recording>,
+        ) {
+            if top_outs.iter().count() == 0 {
+                return;
+            }
+
+            if let Some(path) = &launch.record {
+                match recording.0.save(path) {
+                    Ok(()) => info!("saved the replay to {}", path.display()),
+                    Err(e) => error!("{}", e),
+                }
+            }
+
+            *record = Record {
+                score: scoring.score(),
+                lines: progress.lines(),
+                level: progress.level(),
+                time: stats.time.elapsed(),
+                pieces: stats.pieces,
+                seed: queue.seed,
+            };
+
+            match stage.set(Stage::GameOver) {
+                // quitting with the escape gesture goes first
+                Err(StateError::StateAlreadyQueued) => {},
+                result => result.unwrap(),
+            }
+        }
+
+        /// inputs of a game, with the time of each frame to play it back the
+        /// same
+        #[derive(Clone, Default, Serialize, Deserialize)]
+        pub struct Replay {
+            pub seed: u64,
+            pub rule: RuleConfig,
+            pub frames: Vec<Frame>,
+        }
+        impl Replay {
+            pub fn load(path: &Path) -> Result<Self, FileError> {
+                let replay: Self = read_ron(path)?;
+                replay
+                    .rule
+                    .check()
+                    .map_err(|e| FileError::Invalid(path.to_path_buf(), e))?;
+
+                Ok(replay)
+            }
+
+            fn save(&self, path: &Path) -> Result<(), FileError> { write_ron(path, self) }
+        }
+
+        #[derive(Clone, Serialize, Deserialize)]
+        pub struct Frame {
+            delta: Duration,
+            inputs: Vec<FallingInput>,
+        }
+
+        /// time passed in this frame of the game, taken from the replay while
+        /// playing back
+        #[derive(Default)]
+        struct Clock {
+            delta: Duration,
+        }
+
+        #[derive(Default)]
+        struct Playback {
+            /// frames played back
+            frames: usize,
+            inputs: Vec<FallingInput>,
+        }
+
+        #[derive(Default)]
+        struct Recording(Replay);
+
+        fn tick_clock(
+            time: Res<Time>,
+            launch: Res<Launch>,
+            mut clock: ResMut<Clock>,
+            mut playback: ResMut<Playback>,
+            mut recording: ResMut<Recording>,
+            mut top_outs: EventWriter<TopOut>,
+        ) {
+            clock.delta = match &launch.replay {
+                Some(replay) => match replay.frames.get(playback.frames) {
+                    Some(frame) => {
+                        playback.frames += 1;
+                        playback.inputs = frame.inputs.clone();
+                        frame.delta
+                    },
+                    // the replay has ended
+                    None => {
+                        top_outs.send(TopOut);
+                        Duration::ZERO
+                    },
+                },
+                None => time.delta(),
+            };
+
+            recording.0.frames.push(Frame {
+                delta: clock.delta,
+                inputs: Vec::new(),
+            });
+        }
+
+        fn replay_inputs(mut playback: ResMut<Playback>, mut inputs: EventWriter<FallingInput>) {
+            for input in playback.inputs.drain(..) {
+                inputs.send(input);
+            }
+        }
+
+        fn record_inputs(mut inputs: EventReader<FallingInput>, mut recording: ResMut<Recording>) {
+            if let Some(frame) = recording.0.frames.last_mut() {
+                frame.inputs.extend(inputs.iter().copied());
+            }
+        }
+
+        struct LinesCleared {
+            count: usize,
+            rows: Vec<usize>,
+        }
+
+        fn log_lines_cleared(mut clears: EventReader<LinesCleared>) {
+            for clear in clears.iter() {
+                bevy::log::info!("cleared {} lines {:?}", clear.count, clear.rows);
+            }
+        }
+
+        const MAX_PREVIEWS: usize = 6;
+
+        struct Queue {
+            seed: u64,
+            randomizer: Box<dyn Randomizer>,
+            previews: VecDeque<Kind>,
+        }
+        impl Queue {
+            fn new(kind: RandomizerKind, seed: u64, previews: usize) -> Self {
+                let mut randomizer = kind.build(seed);
+                let previews = (0..previews.min(MAX_PREVIEWS))
+                    .map(|_| randomizer.next())
+                    .collect();
+
+                Self {
+                    seed,
+                    randomizer,
+                    previews,
+                }
+            }
+
+            fn next(&mut self) -> Piece {
+                self.previews.push_back(self.randomizer.next());
+                let kind = self.previews.pop_front().unwrap();
+
+                Piece::new(kind, SPAWN_POSITION.0, SPAWN_POSITION.1)
+            }
+        }
+
+        fn spawn_falling(commands: &mut Commands, piece: Piece) {
+            let (x, y) = transform_as_in_area(piece.x as f32, piece.y as f32);
+
+            commands
+                .spawn()
+                .insert(AreaEntity)
+                .insert(FallingEntity)
+                .insert(Falling(piece))
+                .insert(Locking(Lock::new(piece.y)))
+                .insert_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::OLIVE,
+                        custom_size: Some(Vec2::new(10.0, 10.0)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(x, y, 1.0),
+                    ..default()
+                })
+                .with_children(|cb| {
+                    for (x, y) in piece.offsets().into_iter() {
+                        cb.spawn().insert_bundle(SpriteBundle {
+                            transform: Transform::from_xyz(
+                                x as f32 * BLOCK_SIZE,
+                                y as f32 * BLOCK_SIZE,
+                                0.0,
+                            ),
+                            sprite: Sprite {
+                                color: color_of(piece.kind),
+                                custom_size: Some(Vec2::new(BLOCK_SIZE, BLOCK_SIZE)),
+                                ..default()
+                            },
+                            ..default()
+                        });
+                    }
+                });
+        }
+
+        fn render_falling(
+            mut parents: Query<(&Falling, &mut Transform, &Children), Changed<Falling>>,
+            mut blocks: Query<(&mut Transform, &mut Sprite), Without<Falling>>,
+        ) {
+            for (falling, mut transform, children) in parents.iter_mut() {
+                let (x, y) = transform_as_in_area(falling.0.x as f32, falling.0.y as f32);
+                transform.translation.x = x;
+                transform.translation.y = y;
+
+                for (child, (x, y)) in children.iter().zip(falling.0.offsets()) {
+                    if let Ok((mut transform, mut sprite)) = blocks.get_mut(*child) {
+                        transform.translation.x = x as f32 * BLOCK_SIZE;
+                        transform.translation.y = y as f32 * BLOCK_SIZE;
+                        // swapped by holding
+                        sprite.color = color_of(falling.0.kind);
+                    }
+                }
+            }
+        }
+
+        const PREVIEW_BLOCK_SIZE: f32 = BLOCK_SIZE / 2.0;
+        // each preview takes 3 rows, with the same margin around
+        const PREVIEW_SPACING: f32 = PREVIEW_BLOCK_SIZE * 3.0;
+        const PREVIEW_POSITION: (f32, f32) = (
+            AREA_SIZE.0 / 2.0 + BLOCK_SIZE * 2.0,
+            AREA_SIZE.1 / 2.0 - PREVIEW_SPACING / 2.0,
+        );
+
+        #[derive(Component, Clone, Copy)]
+        struct PreviewEntity;
+
+        fn render_previews(
+            mut commands: Commands,
+            queue: Res<Queue>,
+            entities: Query<(Entity, &PreviewEntity)>,
+        ) {
+            if !queue.is_changed() {
+                return;
+            }
+
+            for (entity, _) in entities.iter() {
+                commands.entity(entity).despawn();
+            }
+
+            if queue.previews.is_empty() {
+                return;
+            }
+
+            let height = PREVIEW_SPACING * (queue.previews.len() as f32 + 1.0);
+            commands
+                .spawn()
+                .insert(AreaEntity)
+                .insert(PreviewEntity)
+                .insert_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::BLACK,
+                        custom_size: Some(Vec2::new(PREVIEW_SPACING * 2.0, height)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(
+                        PREVIEW_POSITION.0,
+                        PREVIEW_POSITION.1 + PREVIEW_SPACING / 2.0 - height / 2.0,
+                        0.0,
+                    ),
+                    ..default()
+                });
+
+            for (index, kind) in queue.previews.iter().enumerate() {
+                let centre = (
+                    PREVIEW_POSITION.0,
+                    PREVIEW_POSITION.1 - PREVIEW_SPACING * index as f32,
+                );
+
+                spawn_mini_piece(&mut commands, PreviewEntity, *kind, color_of(*kind), centre);
+            }
+        }
+
+        fn spawn_mini_piece(
+            commands: &mut Commands,
+            marker: impl Component + Copy,
+            kind: Kind,
+            color: Color,
+            centre: (f32, f32),
+        ) {
+            for (x, y) in centered(Piece::new(kind, 0, 0).offsets()) {
+                commands
+                    .spawn()
+                    .insert(AreaEntity)
+                    .insert(marker)
+                    .insert_bundle(SpriteBundle {
+                        sprite: Sprite {
+                            color,
+                            custom_size: Some(Vec2::new(PREVIEW_BLOCK_SIZE, PREVIEW_BLOCK_SIZE)),
+                            ..default()
+                        },
+                        transform: Transform::from_xyz(
+                            centre.0 + x * PREVIEW_BLOCK_SIZE,
+                            centre.1 + y * PREVIEW_BLOCK_SIZE,
+                            1.0,
+                        ),
+                        ..default()
+                    });
+            }
+        }
+
+        const HOLD_POSITION: (f32, f32) = (-PREVIEW_POSITION.0, PREVIEW_POSITION.1);
+
+        #[derive(Default)]
+        struct Hold {
+            kind: Option<Kind>,
+            /// already held since the last lock
+            used: bool,
+        }
+
+        #[derive(Component, Clone, Copy)]
+        struct HoldEntity;
+
+        fn handle_hold(
+            mut inputs: EventReader<FallingInput>,
+            mut hold: ResMut<Hold>,
+            mut queue: ResMut<Queue>,
+            board: Res<Board>,
+            mut entities: Query<(&FallingEntity, &mut Falling, &mut Locking)>,
+            mut top_outs: EventWriter<TopOut>,
+        ) {
+            for input in inputs.iter() {
+                if !matches!(input, FallingInput::Hold) || hold.used {
+                    continue;
+                }
+
+                for (_, mut falling, mut locking) in entities.iter_mut() {
+                    let next = match hold.kind.replace(falling.0.kind) {
+                        Some(kind) => Piece::new(kind, SPAWN_POSITION.0, SPAWN_POSITION.1),
+                        None => queue.next(),
+                    };
+                    hold.used = true;
+
+                    // block out
+                    if !board.fits(&next) {
+                        top_outs.send(TopOut);
+                    }
+
+                    falling.0 = next;
+                    locking.0 = Lock::new(next.y);
+                }
+            }
+        }
+
+        fn render_hold(
+            mut commands: Commands,
+            hold: Res<Hold>,
+            entities: Query<(Entity, &HoldEntity)>,
+        ) {
+            if !hold.is_changed() {
+                return;
+            }
+
+            for (entity, _) in entities.iter() {
+                commands.entity(entity).despawn();
+            }
+
+            commands
+                .spawn()
+                .insert(AreaEntity)
+                .insert(HoldEntity)
+                .insert_bundle(SpriteBundle {
+                    sprite: Sprite {
+                        color: Color::BLACK,
+                        custom_size: Some(Vec2::new(PREVIEW_SPACING * 2.0, PREVIEW_SPACING * 2.0)),
+                        ..default()
+                    },
+                    transform: Transform::from_xyz(HOLD_POSITION.0, HOLD_POSITION.1, 0.0),
+                    ..default()
+                });
+
+            if let Some(kind) = hold.kind {
+                let color = match hold.used {
+                    true => Color::DARK_GRAY,
+                    false => color_of(kind),
+                };
+
+                spawn_mini_piece(&mut commands, HoldEntity, kind, color, HOLD_POSITION);
+            }
+        }
+
+        /// offsets moved to be centred on the origin
+        fn centered(offsets: [(i32, i32); 4]) -> [(f32, f32); 4] {
+            let xs = offsets.map(|(x, _)| x);
+            let ys = offsets.map(|(_, y)| y);
+            let centre = (
+                (xs.iter().min().unwrap() + xs.iter().max().unwrap()) as f32 / 2.0,
+                (ys.iter().min().unwrap() + ys.iter().max().unwrap()) as f32 / 2.0,
+            );
+
+            offsets.map(|(x, y)| (x as f32 - centre.0, y as f32 - centre.1))
+        }
+
+        #[derive(Component)]
+        struct GhostEntity;
+
+        fn render_ghost(
+            mut commands: Commands,
+            config: Res<Config>,
+            board: Res<Board>,
+            fallings: Query<&Falling>,
+            changed: Query<&Falling, Changed<Falling>>,
+            entities: Query<(Entity, &GhostEntity)>,
+        ) {
+            if !config.view.ghost || (!board.is_changed() && changed.is_empty()) {
+                return;
+            }
+
+            for (entity, _) in entities.iter() {
+                commands.entity(entity).despawn();
+            }
+
+            for falling in fallings.iter() {
+                let ghost = board.project(&falling.0);
+
+                for (x, y) in ghost.cells() {
+                    let (x, y) = transform_as_in_area(x as f32, y as f32);
+
+                    commands
+                        .spawn()
+                        .insert(AreaEntity)
+                        .insert(GhostEntity)
+                        .insert_bundle(SpriteBundle {
+                            sprite: Sprite {
+                                color: *color_of(falling.0.kind).set_a(0.3),
+                                custom_size: Some(Vec2::new(BLOCK_SIZE, BLOCK_SIZE)),
+                                ..default()
+                            },
+                            transform: Transform::from_xyz(x, y, 0.5),
+                            ..default()
+                        });
+                }
+            }
+        }
+
+        fn render_board(
+            mut commands: Commands,
+            board: Res<Board>,
+            entities: Query<(Entity, &MinoEntity)>,
+        ) {
+            if !board.is_changed() {
+                return;
+            }
+
+            for (entity, _) in entities.iter() {
+                commands.entity(entity).despawn();
+            }
+
+            for (x, y, kind) in board.iter() {
+                let (x, y) = transform_as_in_area(x as f32, y as f32);
+
+                commands
+                    .spawn()
+                    .insert(AreaEntity)
+                    .insert(MinoEntity)
+                    .insert_bundle(SpriteBundle {
+                        sprite: Sprite {
+                            color: color_of(kind),
+                            custom_size: Some(Vec2::new(BLOCK_SIZE, BLOCK_SIZE)),
+                            ..default()
+                        },
+                        transform: Transform::from_xyz(x, y, 1.0),
+                        ..default()
+                    });
+            }
+        }
+
+        #[derive(Clone, Copy, Serialize, Deserialize)]
+        pub enum FallingInput {
+            Left,
+            Right,
+            SoftDrop,
+            HardDrop,
+            P90Spin,
+            N90Spin,
+            P180Spin,
+            Hold,
+        }
+
+        #[derive(Default)]
+        struct Repeats {
+            left: Repeat,
+            right: Repeat,
+            soft_drop: Repeat,
+        }
+
+        fn falling_input(
+            controls: Controls,
+            clock: Res<Clock>,
+            launch: Res<Launch>,
+            mut inputs: EventWriter<FallingInput>,
+            mut repeats: ResMut<Repeats>,
+            config: Res<Config>,
+            progress: Res<Progress>,
+        ) {
+            // from the replay instead
+            if launch.replay.is_some() {
+                return;
+            }
+
+            let handling = &config.handling;
+            let das = Duration::from_millis(handling.das);
+            let arr = Duration::from_millis(handling.arr);
+            // soft drops repeat as fast as the accelerated gravity
+            let soft_drop_arr = match (
+                config.rule.soft_drop,
+                config.rule.gravity.get(progress.level()),
+            ) {
+                (SoftDrop::Factor(factor), Gravity::FramesPerRow(frames)) =>
+                    Duration::try_from_secs_f32(frames / FRAMES_PER_SEC / factor)
+                        .unwrap_or(Duration::MAX),
+                _ => Duration::ZERO,
+            };
+
+            let delta = clock.delta;
+
+            let steps = repeats.left.tick(
+                controls.presses(Binding::Left),
+                controls.pressed(Binding::Left),
+                delta,
+                das,
+                arr,
+                AREA_BLOCKS.0,
+            );
+            let left = vec![FallingInput::Left; steps];
+            let steps = repeats.right.tick(
+                controls.presses(Binding::Right),
+                controls.pressed(Binding::Right),
+                delta,
+                das,
+                arr,
+                AREA_BLOCKS.0,
+            );
+            let right = vec![FallingInput::Right; steps];
+            let steps = repeats.soft_drop.tick(
+                controls.presses(Binding::SoftDrop),
+                controls.pressed(Binding::SoftDrop),
+                delta,
+                Duration::from_millis(handling.soft_drop_das),
+                soft_drop_arr,
+                AREA_BLOCKS.1 + BUFFER_BLOCKS,
+            );
+            let soft_drops = vec![FallingInput::SoftDrop; steps];
+
+            // unbound 180 spins are never pressed
+            let spins = [
+                (Binding::P90Spin, FallingInput::P90Spin),
+                (Binding::N90Spin, FallingInput::N90Spin),
+                (Binding::P180Spin, FallingInput::P180Spin),
+            ];
+            let spun = !controls.just_pressed(&spins).is_empty();
+
+            // holds and spins first, to shift and drop the piece after them
+            let mut pressed = controls.just_pressed(&[(Binding::Hold, FallingInput::Hold)]);
+            pressed.extend(controls.just_pressed(&spins));
+            pressed.extend(left);
+            pressed.extend(right);
+            pressed.extend(soft_drops);
+            pressed.extend(controls.just_pressed(&[(Binding::HardDrop, FallingInput::HardDrop)]));
+
+            for input in pressed {
+                inputs.send(input);
+            }
+
+            if spun && handling.das_cut > 0 {
+                let cut = Duration::from_millis(handling.das_cut);
+                repeats.left.cut(das, cut);
+                repeats.right.cut(das, cut);
+            }
+        }
+
+        fn falling_handle(
+            mut inputs: EventReader<FallingInput>,
+            config: Res<Config>,
+            mut entities: Query<(&FallingEntity, &mut Falling, &mut Locking)>,
+            mut landings: EventWriter<Landing>,
+            mut drops: EventWriter<Dropped>,
+            board: Res<Board>,
+        ) {
+            for input in inputs.iter() {
+                for (_, mut falling, mut locking) in entities.iter_mut() {
+                    let rotation = falling.0.rotation;
+                    let moved = match *input {
+                        FallingInput::Left => Some(falling.0.shifted(-1, 0)),
+                        FallingInput::Right => Some(falling.0.shifted(1, 0)),
+                        FallingInput::SoftDrop => {
+                            let moved = falling.0.shifted(0, -1);
+
+                            // locking is left to the gravity
+                            if board.fits(&moved) {
+                                drops.send(Dropped {
+                                    rows: 1,
+                                    hard: false,
+                                });
+                            }
+
+                            Some(moved)
+                        },
+                        FallingInput::HardDrop => {
+                            let projected = board.project(&falling.0);
+
+                            drops.send(Dropped {
+                                rows: (falling.0.y - projected.y) as u32,
+                                hard: true,
+                            });
+                            landings.send(Landing);
+
+                            Some(projected)
+                        },
+                        FallingInput::P90Spin => srs::rotate(&board, &falling.0, rotation.ccw()),
+                        FallingInput::N90Spin => srs::rotate(&board, &falling.0, rotation.cw()),
+                        FallingInput::P180Spin => srs::rotate(&board, &falling.0, rotation.half()),
+                        FallingInput::Hold => None,
+                    };
+
+                    if let Some(moved) = moved.filter(|moved| board.fits(moved)) {
+                        falling.0 = moved;
+
+                        if !matches!(input, FallingInput::SoftDrop | FallingInput::HardDrop) {
+                            locking.0.moved(&config.rule.lock_delay);
+                        }
+                    }
+                }
+            }
+        }
+
+        fn despawn_area(mut commands: Commands, entities: Query<(Entity, &AreaEntity)>) {
+            for (entity, _) in entities.iter() {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+
+        fn pause_input(controls: Controls, mut stage: ResMut<State<Stage>>) {
+            if controls.any_just_pressed([Binding::Pause]) {
+                match stage.push(Stage::Pause) {
+                    // a top out or quitting in this frame goes first
+                    Err(StateError::StateAlreadyQueued) => {},
+                    result => result.unwrap(),
+                }
+            }
+        }
+
+        type InArea = Or<(With<AreaEntity>, With<UiEntity>)>;
+
+        // not to look at the board while paused
+        fn hide_area(
+            roots: Query<Entity, InArea>,
+            children: Query<&Children>,
+            visibilities: Query<&mut Visibility>,
+        ) {
+            set_area_visibility(false, roots, children, visibilities);
+        }
+
+        fn show_area(
+            roots: Query<Entity, InArea>,
+            children: Query<&Children>,
+            visibilities: Query<&mut Visibility>,
+        ) {
+            set_area_visibility(true, roots, children, visibilities);
+        }
+
+        // visibility is not inherited, so the blocks of pieces are also set
+        fn set_area_visibility(
+            is_visible: bool,
+            roots: Query<Entity, InArea>,
+            children: Query<&Children>,
+            mut visibilities: Query<&mut Visibility>,
+        ) {
+            let mut entities: Vec<_> = roots.iter().collect();
+
+            while let Some(entity) = entities.pop() {
+                if let Ok(mut visibility) = visibilities.get_mut(entity) {
+                    visibility.is_visible = is_visible;
+                }
+                if let Ok(children) = children.get(entity) {
+                    entities.extend(children.iter());
+                }
+            }
+        }
+
+        #[derive(Component)]
+        struct AreaEntity;
+    }
+
+    pub mod pause {
+        use bevy::app::Plugin as PluginTrait;
+        use bevy::prelude::*;
+
+        use crate::menu::{self, Menu, MenuEvent};
+        use crate::Stage::Pause as SelfStage;
+        use crate::{clear_input, AssetStore, Config, Launch, Locale, Message, Stage};
+
+        pub struct Plugin;
+        impl PluginTrait for Plugin {
+            fn name(&self) -> &str { "pause" }
+
+            fn build(&self, app: &mut App) {
+                app.add_plugin(menu::Plugin::<Action>::new(SelfStage));
+
+                app.add_system_set(
+                    SystemSet::on_enter(SelfStage)
+                        .with_system(spawn_ui)
+                        .with_system(clear_input),
+                );
+                app.add_system_set(SystemSet::on_update(SelfStage).with_system(handle_menu));
+                app.add_system_set(SystemSet::on_exit(SelfStage).with_system(despawn_ui));
+
+                // the settings are pushed over the pause
+                app.add_system_set(SystemSet::on_pause(SelfStage).with_system(despawn_ui));
+                app.add_system_set(
+                    SystemSet::on_resume(SelfStage)
+                        .with_system(spawn_ui)
+                        .with_system(clear_input),
+                );
+            }
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        enum Action {
+            Resume,
+            Restart,
+            Settings,
+            Title,
+        }
+
+        fn spawn_ui(
+            mut commands: Commands,
+            assets: Res<AssetStore>,
+            config: Res<Config>,
+            locale: Res<Locale>,
+            launch: Res<Launch>,
+        ) {
+            commands
+                .spawn()
+                .insert(UiEntity)
+                .insert_bundle(UiCameraBundle::default());
+
+            let theme = &config.theme;
+
+            let root = commands
+                .spawn()
+                .insert(UiEntity)
+                .insert_bundle(NodeBundle {
+                    style: Style {
+                        size: Size {
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(100.0),
+                        },
+                        flex_direction: FlexDirection::ColumnReverse,
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    color: UiColor(Color::NONE),
+                    ..default()
+                })
+                .with_children(|cb| {
+                    cb.spawn().insert(UiEntity).insert_bundle(TextBundle {
+                        text: Text::with_section(
+                            locale.get(Message::Paused),
+                            theme.heading.style(&assets, Color::ANTIQUE_WHITE),
+                            TextAlignment {
+                                vertical: VerticalAlign::Center,
+                                horizontal: HorizontalAlign::Center,
+                            },
+                        ),
+                        style: Style {
+                            margin: Rect {
+                                bottom: Val::Px(32.0),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        ..default()
+                    });
+                })
+                .id();
+
+            let item = |message, action| menu::Item::new(locale.get(message), action);
+            let submit = menu::Action::Submit;
+
+            // a replay would only be played back again, so it ends instead
+            let title = match launch.replay {
+                Some(_) => Message::Quit,
+                None => Message::Title,
+            };
+            let items = vec![
+                item(Message::Resume, submit(Action::Resume)),
+                item(Message::Restart, submit(Action::Restart)).enabled(launch.replay.is_none()),
+                item(Message::Settings, submit(Action::Settings)),
+                item(
+                    title,
+                    menu::Action::Open(vec![
+                        item(Message::Quit, submit(Action::Title)),
+                        item(Message::Back, menu::Action::Back),
+                    ]),
+                ),
+            ];
+            let menu = Menu {
+                items,
+                wrap: true,
+                item: theme.item,
+                selected: theme.selected,
+            };
+            menu::spawn(&mut commands, root, menu, &assets);
+        }
+
+        #[derive(Component)]
+        struct UiEntity;
+
+        fn handle_menu(
+            mut events: EventReader<MenuEvent<Action>>,
+            launch: Res<Launch>,
+            mut stage: ResMut<State<Stage>>,
+        ) {
+            // the title would start the replay again, with its rules
+            let title = match launch.replay {
+                Some(_) => Stage::End,
+                None => Stage::Title,
+            };
+
+            // replacing unwinds the game under the pause too
+            match events.iter().next() {
+                Some(MenuEvent::Submit(Action::Resume) | MenuEvent::Back) => stage.pop().unwrap(),
+                Some(MenuEvent::Submit(Action::Restart)) => stage.replace(Stage::Game).unwrap(),
+                Some(MenuEvent::Submit(Action::Settings)) => stage.push(Stage::Settings).unwrap(),
+                Some(MenuEvent::Submit(Action::Title)) => stage.replace(title).unwrap(),
+                None => (),
+            }
+        }
+
+        fn despawn_ui(mut commands: Commands, entities: Query<(Entity, &UiEntity)>) {
+            for (entity, _) in entities.iter() {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+
+    pub mod game_over {
+        use std::time::Duration;
+
+        use bevy::app::{AppExit, Plugin as PluginTrait};
+        use bevy::prelude::*;
+
+        use crate::menu::{self, Menu, MenuEvent};
+        use crate::stag::game::Record;
+        use crate::Stage::GameOver as SelfStage;
+        use crate::{clear_input, AssetStore, Config, Language, Launch, Locale, Message, Stage};
+
+        pub struct Plugin;
+        impl PluginTrait for Plugin {
+            fn name(&self) -> &str { "game_over" }
+
+            fn build(&self, app: &mut App) {
+                app.add_plugin(menu::Plugin::<Action>::new(SelfStage));
+
+                app.add_system_set(
+                    SystemSet::on_enter(SelfStage)
+                        .with_system(spawn_ui)
+                        .with_system(clear_input)
+                        .with_system(exit_headless),
+                );
+                app.add_system_set(SystemSet::on_update(SelfStage).with_system(handle_menu));
+                app.add_system_set(SystemSet::on_exit(SelfStage).with_system(despawn_ui));
+            }
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        enum Action {
+            Retry,
+            Title,
+        }
+
+        fn spawn_ui(
+            mut commands: Commands,
+            assets: Res<AssetStore>,
+            config: Res<Config>,
+            locale: Res<Locale>,
+            launch: Res<Launch>,
+            record: Res<Record>,
+        ) {
+            commands
+                .spawn()
+                .insert(UiEntity)
+                .insert_bundle(UiCameraBundle::default());
+
+            let theme = &config.theme;
+
+            let results = results(&record);
+
+            let root = commands
+                .spawn()
+                .insert(UiEntity)
+                .insert_bundle(NodeBundle {
+                    style: Style {
+                        size: Size {
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(100.0),
+                        },
+                        flex_direction: FlexDirection::ColumnReverse,
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    color: UiColor(Color::NONE),
+                    ..default()
+                })
+                .with_children(|cb| {
+                    cb.spawn().insert(UiEntity).insert_bundle(TextBundle {
+                        text: Text::with_section(
+                            locale.get(Message::GameOver),
+                            theme.heading.style(&assets, Color::ANTIQUE_WHITE),
+                            TextAlignment {
+                                vertical: VerticalAlign::Center,
+                                horizontal: HorizontalAlign::Center,
+                            },
+                        ),
+                        style: Style {
+                            margin: Rect {
+                                bottom: Val::Px(32.0),
+                                ..default()
+                            },
+                            ..default()
+                        },
+                        ..default()
+                    });
+
+                    for (label, value) in results {
+                        cb.spawn().insert(UiEntity).insert_bundle(TextBundle {
+                            text: Text::with_section(
+                                format!("{}  {}", locale.get(label), value),
+                                theme.label.style(&assets, Color::GRAY),
+                                TextAlignment {
+                                    vertical: VerticalAlign::Center,
+                                    horizontal: HorizontalAlign::Center,
+                                },
+                            ),
+                            ..default()
+                        });
+                    }
+                })
+                .id();
+
+            let parent = commands
+                .spawn()
+                .insert(UiEntity)
+                .insert_bundle(NodeBundle {
+                    style: Style {
+                        margin: Rect {
+                            top: Val::Px(32.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    color: UiColor(Color::NONE),
+                    ..default()
+                })
+                .id();
+            commands.entity(root).add_child(parent);
+
+            // a replay would only be played back again, so it ends instead
+            let title = match launch.replay {
+                Some(_) => Message::Quit,
+                None => Message::Title,
+            };
+            let items = vec![
+                menu::Item::new(
+                    locale.get(Message::Retry),
+                    menu::Action::Submit(Action::Retry),
+                )
+                .enabled(launch.replay.is_none()),
+                menu::Item::new(locale.get(title), menu::Action::Submit(Action::Title)),
+            ];
+            let menu = Menu {
+                items,
+                wrap: true,
+                item: theme.item,
+                selected: theme.selected,
+            };
+            menu::spawn(&mut commands, parent, menu, &assets);
+        }
+
+        fn results(record: &Record) -> [(Message, String); 6] {
+            [
+                (Message::Score, record.score.to_string()),
+                (Message::Lines, record.lines.to_string()),
+                (Message::Level, record.level.to_string()),
+                (Message::Time, format_time(record.time)),
+                (Message::Pieces, record.pieces.to_string()),
+                (Message::Seed, record.seed.to_string()),
+            ]
+        }
+
+        // to script simulations with the results
+        fn exit_headless(launch: Res<Launch>, record: Res<Record>, mut exit: EventWriter<AppExit>) {
+            if !launch.headless {
+                return;
+            }
+
+            // in english whatever the config, not to break the scripts
+            let locale = Locale::new(Language::En);
+            for (label, value) in results(&record) {
+                println!("{}  {}", locale.get(label), value);
+            }
+
+            exit.send(AppExit);
+        }
+
+        fn format_time(time: Duration) -> String {
+            let centis = time.as_millis() / 10;
+
+            format!(
+                "{:02}:{:02}.{:02}",
+                centis / 6000,
+                centis / 100 % 60,
+                centis % 100
+            )
+        }
+
+        #[derive(Component)]
+        struct UiEntity;
+
+        fn handle_menu(
+            mut events: EventReader<MenuEvent<Action>>,
+            launch: Res<Launch>,
+            mut stage: ResMut<State<Stage>>,
+        ) {
+            // the title would start the replay again, with its rules
+            let title = match launch.replay {
+                Some(_) => Stage::End,
+                None => Stage::Title,
+            };
+
+            match events.iter().next() {
+                Some(MenuEvent::Submit(Action::Retry)) => stage.set(Stage::Game).unwrap(),
+                Some(MenuEvent::Submit(Action::Title)) => stage.set(title).unwrap(),
+                Some(MenuEvent::Back) | None => (),
+            }
+        }
+
+        fn despawn_ui(mut commands: Commands, entities: Query<(Entity, &UiEntity)>) {
+            for (entity, _) in entities.iter() {
+                commands.entity(entity).despawn();
+            }
+        }
+    }
+
+    pub mod end {
+        use bevy::app::{AppExit, Plugin as PluginTrait};
+        use bevy::prelude::*;
+
+        use crate::Stage::End as SelfStage;
+
+        pub struct Plugin;
+        impl PluginTrait for Plugin {
+            fn name(&self) -> &str { "end" }
+
+            fn build(&self, app: &mut App) {
+                app.add_system_set(SystemSet::on_enter(SelfStage).with_system(stop_app));
+            }
+        }
+
+        fn stop_app(mut exit: EventWriter<AppExit>) { exit.send(AppExit); }
+    }
+}
+
+/// lists of items moved through with the title bindings, shared by the stages
+mod menu {
+    use std::marker::PhantomData;
+
+    use bevy::app::Plugin as PluginTrait;
+    use bevy::prelude::*;
+
+    use crate::{AssetStore, Binding, Controls, Stage, TextRole};
+
+    /// navigates the menus with actions of `A` in the stage
+    pub struct Plugin<A> {
+        stage: Stage,
+        marker: PhantomData<fn() -> A>,
+    }
+    impl<A> Plugin<A> {
+        pub fn new(stage: Stage) -> Self {
+            Self {
+                stage,
+                marker: PhantomData,
+            }
+        }
+    }
+    impl<A: Copy + PartialEq + Send + Sync + 'static> PluginTrait for Plugin<A> {
+        fn name(&self) -> &str { "menu" }
+
+        fn build(&self, app: &mut App) {
+            app.add_event::<MenuEvent<A>>();
+            app.insert_resource(Last::<A>(None));
+
+            app.add_system_set(SystemSet::on_enter(self.stage).with_system(forget::<A>));
+            app.add_system_set(
+                SystemSet::on_update(self.stage)
+                    .with_system(restore::<A>.before(Navigation))
+                    .with_system(navigate::<A>.label(Navigation))
+                    .with_system(update_ui::<A>.after(Navigation)),
+            );
+            app.add_system_set(SystemSet::on_exit(self.stage).with_system(despawn));
+            app.add_system_set(SystemSet::on_pause(self.stage).with_system(despawn));
+        }
+    }
+
+    /// stages reading the input themselves order around the navigation
+    #[derive(Clone, Debug, PartialEq, Eq, Hash, SystemLabel)]
+    pub struct Navigation;
+
+    /// what the root menu tells the stage
+    pub enum MenuEvent<A> {
+        Submit(A),
+        /// backed out of the root menu
+        Back,
+    }
+
+    /// the menu to spawn
+    pub struct Menu<A> {
+        pub items: Vec<Item<A>>,
+        /// from the last item to the first and back
+        pub wrap: bool,
+        pub item: TextRole,
+        pub selected: TextRole,
+    }
+
+    pub struct Item<A> {
+        label: String,
+        action: Action<A>,
+        enabled: bool,
+    }
+    impl<A> Item<A> {
+        pub fn new(label: String, action: Action<A>) -> Self {
+            Self {
+                label,
+                action,
+                enabled: true,
+            }
+        }
+
+        /// disabled items are shown but skipped by the cursor
+        pub fn enabled(self, enabled: bool) -> Self { Self { enabled, ..self } }
+    }
+
+    pub enum Action<A> {
+        Submit(A),
+        /// a submenu shown in place of the menu
+        Open(Vec<Item<A>>),
+        /// to the parent menu, or out of the root one
+        Back,
+    }
+
+    /// the item submitted last, selected again when the menu is spawned back
+    struct Last<A>(Option<A>);
+
+    #[derive(Component)]
+    pub struct MenuEntity {
+        /// an enabled item, if any
+        selected: Option<Entity>,
+        wrap: bool,
+        /// only one menu is shown at a time
+        active: bool,
+        parent: Option<Entity>,
+        /// the stage takes the input for the selected item meanwhile
+        pub editing: bool,
+        item_role: TextRole,
+        selected_role: TextRole,
+    }
+
+    #[derive(Component)]
+    pub struct ItemEntity<A> {
+        effect: Effect<A>,
+        enabled: bool,
+    }
+    impl<A: Copy> ItemEntity<A> {
+        pub fn action(&self) -> Option<A> {
+            match self.effect {
+                Effect::Submit(action) => Some(action),
+                _ => None,
+            }
+        }
+    }
+
+    enum Effect<A> {
+        Submit(A),
+        Open(Entity),
+        Back,
+    }
+
+    const DISABLED_COLOR: Color = Color::rgba(0.25, 0.25, 0.25, 0.4);
+
+    /// the root menu and its submenus as children of `parent`
+    pub fn spawn<A: Send + Sync + 'static>(
+        commands: &mut Commands,
+        parent: Entity,
+        menu: Menu<A>,
+        assets: &AssetStore,
+    ) -> Entity {
+        let Menu {
+            items,
+            wrap,
+            item,
+            selected,
+        } = menu;
+        let spawner = Spawner {
+            parent,
+            wrap,
+            item_role: item,
+            selected_role: selected,
+            assets,
+        };
+
+        spawner.spawn(commands, items, None)
+    }
+
+    struct Spawner<'a> {
+        parent: Entity,
+        wrap: bool,
+        item_role: TextRole,
+        selected_role: TextRole,
+        assets: &'a AssetStore,
+    }
+    impl Spawner<'_> {
+        fn spawn<A: Send + Sync + 'static>(
+            &self,
+            commands: &mut Commands,
+            items: Vec<Item<A>>,
+            parent: Option<Entity>,
+        ) -> Entity {
+            let menu = commands.spawn().id();
+            commands.entity(self.parent).add_child(menu);
+
+            let mut selected = None;
+            for item in items {
+                let effect = match item.action {
+                    Action::Submit(action) => Effect::Submit(action),
+                    // submenus are siblings of the menu
+                    Action::Open(items) => Effect::Open(self.spawn(commands, items, Some(menu))),
+                    Action::Back => Effect::Back,
+                };
+
+                let entity = commands
+                    .spawn()
+                    .insert(ItemEntity {
+                        effect,
+                        enabled: item.enabled,
+                    })
+                    .insert_bundle(TextBundle {
+                        text: Text::with_section(
+                            item.label,
+                            self.item_role.style(self.assets, Color::NONE),
+                            TextAlignment {
+                                vertical: VerticalAlign::Center,
+                                horizontal: HorizontalAlign::Center,
+                            },
+                        ),
+                        ..default()
+                    })
+                    .id();
+                commands.entity(menu).add_child(entity);
+
+                if selected.is_none() && item.enabled {
+                    selected = Some(entity);
+                }
+            }
+
+            commands
+                .entity(menu)
+                .insert(MenuEntity {
+                    selected,
+                    wrap: self.wrap,
+                    active: parent.is_none(),
+                    parent,
+                    editing: false,
+                    item_role: self.item_role,
+                    selected_role: self.selected_role,
+                })
+                .insert_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::ColumnReverse,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    color: UiColor(Color::NONE),
+                    ..default()
+                });
+
+            menu
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    enum MenuInput {
+        Up,
+        Down,
+        Submit,
+        Back,
+    }
+
+    fn forget<A: Send + Sync + 'static>(mut last: ResMut<Last<A>>) { last.0 = None; }
+
+    fn restore<A: Copy + PartialEq + Send + Sync + 'static>(
+        last: Res<Last<A>>,
+        mut menus: Query<(&mut MenuEntity, &Children), Added<MenuEntity>>,
+        items: Query<&ItemEntity<A>>,
+    ) {
+        let Some(action) = last.0 else {
+            return;
+        };
+
+        for (mut menu, children) in menus.iter_mut() {
+            let found = children.iter().copied().find(|child| {
+                items
+                    .get(*child)
+                    .is_ok_and(|item| item.enabled && item.action() == Some(action))
+            });
+            if found.is_some() {
+                menu.selected = found;
+            }
+        }
+    }
+
+    fn navigate<A: Copy + Send + Sync + 'static>(
+        controls: Controls,
+        mut menus: Query<(&mut MenuEntity, &Children)>,
+        items: Query<&ItemEntity<A>>,
+        mut events: EventWriter<MenuEvent<A>>,
+        mut last: ResMut<Last<A>>,
+    ) {
+        let Some((mut menu, children)) = menus
+            .iter_mut()
+            .find(|(menu, _)| menu.active && !menu.editing)
+        else {
+            return;
+        };
+
+        let enabled: Vec<_> = children
+            .iter()
+            .copied()
+            .filter(|child| items.get(*child).is_ok_and(|item| item.enabled))
+            .collect();
+
+        let pressed = controls.just_pressed(&[
+            (Binding::TitleUp, MenuInput::Up),
+            (Binding::TitleDown, MenuInput::Down),
+            (Binding::TitleSubmit, MenuInput::Submit),
+            (Binding::TitleBack, MenuInput::Back),
+        ]);
+
+        // the menu to show instead, and whether to select its first item
+        let mut switch = None;
+        for input in pressed {
+            let effect = match input {
+                MenuInput::Up | MenuInput::Down => {
+                    let forward = matches!(input, MenuInput::Down);
+                    menu.selected = step(&enabled, menu.selected, forward, menu.wrap);
+                    continue;
+                },
+                MenuInput::Submit => {
+                    let Some(item) = menu.selected.and_then(|selected| items.get(selected).ok())
+                    else {
+                        continue;
+                    };
+                    &item.effect
+                },
+                MenuInput::Back => &Effect::Back,
+            };
+
+            match *effect {
+                Effect::Submit(action) => {
+                    last.0 = Some(action);
+                    events.send(MenuEvent::Submit(action));
+                },
+                Effect::Open(submenu) => switch = Some((submenu, true)),
+                Effect::Back => match menu.parent {
+                    Some(parent) => switch = Some((parent, false)),
+                    None => events.send(MenuEvent::Back),
+                },
+            }
+            break;
+        }
+
+        let Some((target, reset)) = switch else {
+            return;
+        };
+        menu.active = false;
+
+        if let Ok((mut target, children)) = menus.get_mut(target) {
+            target.active = true;
+            if reset {
+                target.selected = children
+                    .iter()
+                    .copied()
+                    .find(|child| items.get(*child).is_ok_and(|item| item.enabled));
+            }
+        }
+    }
+
+    /// the next enabled item, or the first one without a selection
+    fn step(
+        enabled: &[Entity],
+        selected: Option<Entity>,
+        forward: bool,
+        wrap: bool,
+    ) -> Option<Entity> {
+        let Some(index) = selected.and_then(|selected| enabled.iter().position(|e| *e == selected))
+        else {
+            return enabled.first().copied();
+        };
+
+        let len = enabled.len();
+        let index = match (forward, wrap) {
+            (true, true) => (index + 1) % len,
+            (true, false) => (index + 1).min(len - 1),
+            (false, true) => (index + len - 1) % len,
+            (false, false) => index.saturating_sub(1),
+        };
+        Some(enabled[index])
+    }
+
+    fn update_ui<A: Send + Sync + 'static>(
+        assets: Res<AssetStore>,
+        mut menus: Query<(&MenuEntity, &Children, &mut Style, &mut Visibility)>,
+        changed: Query<Entity, Changed<MenuEntity>>,
+        changed_items: Query<&Parent, Changed<ItemEntity<A>>>,
+        mut items: Query<(&ItemEntity<A>, &mut Text, &mut Visibility), Without<MenuEntity>>,
+    ) {
+        let changed = changed
+            .iter()
+            .chain(changed_items.iter().map(|parent| parent.0));
+
+        for entity in changed {
+            let Ok((menu, children, mut style, mut visibility)) = menus.get_mut(entity) else {
+                continue;
+            };
+
+            // hidden menus take no space either
+            style.display = match menu.active {
+                true => Display::Flex,
+                false => Display::None,
+            };
+            visibility.is_visible = menu.active;
+
+            for child in children.iter() {
+                let Ok((item, mut text, mut visibility)) = items.get_mut(*child) else {
+                    continue;
+                };
+                visibility.is_visible = menu.active;
+
+                let (role, color) = match (menu.selected == Some(*child), item.enabled) {
+                    (true, _) if menu.editing => (menu.selected_role, Color::GOLD),
+                    (true, _) => (menu.selected_role, Color::SALMON),
+                    (false, true) => (menu.item_role, Color::DARK_GRAY),
+                    (false, false) => (menu.item_role, DISABLED_COLOR),
+                };
+                for section in text.sections.iter_mut() {
+                    role.apply(&assets, &mut section.style);
+                    section.style.color = color;
+                }
+            }
+        }
+    }
+
+    fn despawn(mut commands: Commands, menus: Query<Entity, With<MenuEntity>>) {
+        for entity in menus.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use bevy::prelude::Entity;
+
+        use super::step;
+
+        #[test]
+        fn step_selects_the_first_without_a_selection() {
+            let [a, b, c, disabled] = [0, 1, 2, 3].map(Entity::from_raw);
+            let enabled = [a, b, c];
+
+            assert_eq!(step(&enabled, None, true, true), Some(a));
+            assert_eq!(step(&enabled, None, false, false), Some(a));
+            assert_eq!(step(&enabled, Some(disabled), false, true), Some(a));
+            assert_eq!(step(&[], None, true, true), None);
+        }
+
+        #[test]
+        fn step_moves_and_wraps() {
+            let [a, b, c] = [0, 1, 2].map(Entity::from_raw);
+            let enabled = [a, b, c];
+
+            assert_eq!(step(&enabled, Some(a), true, false), Some(b));
+            assert_eq!(step(&enabled, Some(b), false, false), Some(a));
+            assert_eq!(step(&enabled, Some(c), true, true), Some(a));
+            assert_eq!(step(&enabled, Some(a), false, true), Some(c));
+        }
+
+        #[test]
+        fn step_stops_at_the_ends_without_wrapping() {
+            let [a, b, c] = [0, 1, 2].map(Entity::from_raw);
+            let enabled = [a, b, c];
+
+            assert_eq!(step(&enabled, Some(c), true, false), Some(c));
+            assert_eq!(step(&enabled, Some(a), false, false), Some(a));
+        }
+    }
+}
+
+mod logic {
+    pub mod piece {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum Kind {
+            I,
+            J,
+            L,
+            O,
+            S,
+            T,
+            Z,
+        }
+        impl Kind {
+            pub const ALL: [Self; 7] = [
+                Self::I,
+                Self::J,
+                Self::L,
+                Self::O,
+                Self::S,
+                Self::T,
+                Self::Z,
+            ];
+
+            fn shape(&self) -> [(i32, i32); 4] {
+                match *self {
+                    Self::I => I,
+                    Self::J => J,
+                    Self::L => L,
+                    Self::O => O,
+                    Self::S => S,
+                    Self::T => T,
+                    Self::Z => Z,
+                }
+            }
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum Rotation {
+            Spawn,
+            Right,
+            Reverse,
+            Left,
+        }
+        impl Rotation {
+            pub fn cw(&self) -> Self {
+                match *self {
+                    Self::Spawn => Self::Right,
+                    Self::Right => Self::Reverse,
+                    Self::Reverse => Self::Left,
+                    Self::Left => Self::Spawn,
+                }
+            }
+
+            pub fn ccw(&self) -> Self {
+                match *self {
+                    Self::Spawn => Self::Left,
+                    Self::Right => Self::Spawn,
+                    Self::Reverse => Self::Right,
+                    Self::Left => Self::Reverse,
+                }
+            }
+
+            pub fn half(&self) -> Self {
+                match *self {
+                    Self::Spawn => Self::Reverse,
+                    Self::Right => Self::Left,
+                    Self::Reverse => Self::Spawn,
+                    Self::Left => Self::Right,
+                }
+            }
+
+            /// rotates around the pivot, or around the centre of the pivot and
+            /// its upper right cell (`shifted`) for I and O
+            fn apply(&self, (x, y): (i32, i32), shifted: bool) -> (i32, i32) {
+                let s = shifted as i32;
+
+                match *self {
+                    Self::Spawn => (x, y),
+                    Self::Right => (y, s - x),
+                    Self::Reverse => (s - x, s - y),
+                    Self::Left => (s - y, x),
+                }
+            }
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct Piece {
+            pub kind: Kind,
+            pub rotation: Rotation,
+            pub x: i32,
+            pub y: i32,
+        }
+        impl Piece {
+            pub fn new(kind: Kind, x: i32, y: i32) -> Self {
+                Self {
+                    kind,
+                    rotation: Rotation::Spawn,
+                    x,
+                    y,
+                }
+            }
+
+            /// positions of each mino, relative to the pivot
+            pub fn offsets(&self) -> [(i32, i32); 4] {
+                let shifted = matches!(self.kind, Kind::I | Kind::O);
+
+                self.kind
+                    .shape()
+                    .map(|offset| self.rotation.apply(offset, shifted))
+            }
+
+            /// positions of each mino on the board
+            pub fn cells(&self) -> [(i32, i32); 4] {
+                self.offsets().map(|(x, y)| (self.x + x, self.y + y))
+            }
+
+            pub fn shifted(&self, dx: i32, dy: i32) -> Self {
+                Self {
+                    x: self.x + dx,
+                    y: self.y + dy,
+                    ..*self
+                }
+            }
+
+            pub fn rotated(&self, rotation: Rotation) -> Self { Self { rotation, ..*self } }
+        }
+
+        // [+y]
+        // ^
+        // |
+        // + ---> [+x]
+
+        // [-- -- -- --]
+        // [   ++      ]
+        // [           ]
+        // [           ]
+        const I: [(i32, i32); 4] = [(-1, 1), (0, 1), (1, 1), (2, 1)];
+
+        // [--         ]
+        // [-- ** --   ]
+        // [           ]
+        // [           ]
+        const J: [(i32, i32); 4] = [(-1, 1), (-1, 0), (0, 0), (1, 0)];
+
+        // [      --   ]
+        // [-- ** --   ]
+        // [           ]
+        // [           ]
+        const L: [(i32, i32); 4] = [(1, 1), (-1, 0), (0, 0), (1, 0)];
+
+        // [  -- --    ]
+        // [  ** --    ]
+        // [           ]
+        // [           ]
+        const O: [(i32, i32); 4] = [(0, 1), (1, 1), (0, 0), (1, 0)];
+
+        // [   -- --   ]
+        // [-- **      ]
+        // [           ]
+        // [           ]
+        const S: [(i32, i32); 4] = [(0, 1), (1, 1), (-1, 0), (0, 0)];
+
+        // [   --      ]
+        // [-- ** --   ]
+        // [           ]
+        // [           ]
+        const T: [(i32, i32); 4] = [(0, 1), (-1, 0), (0, 0), (1, 0)];
+
+        // [-- --      ]
+        // [   ** --   ]
+        // [           ]
+        // [           ]
+        const Z: [(i32, i32); 4] = [(-1, 1), (0, 1), (0, 0), (1, 0)];
+    }
+
+    pub mod board {
+        use super::piece::{Kind, Piece};
+
+        /// logical playfield; `(0, 0)` is the bottom-left cell
+        pub struct Board {
+            width: usize,
+            height: usize,
+            cells: Vec<Option<Kind>>,
+        }
+        impl Board {
+            pub fn new(width: usize, height: usize) -> Self {
+                Self {
+                    width,
+                    height,
+                    cells: vec![None; width * height],
+                }
+            }
+
+            pub fn height(&self) -> usize { self.height }
+
+            fn index(&self, x: i32, y: i32) -> Option<usize> {
+                if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+                    return None;
+                }
+
+                Some(y as usize * self.width + x as usize)
+            }
+
+            /// out of the board (walls and floor) is occupied
+            pub fn is_occupied(&self, x: i32, y: i32) -> bool {
+                match self.index(x, y) {
+                    Some(index) => self.cells[index].is_some(),
+                    None => true,
+                }
+            }
+
+            pub fn fits(&self, piece: &Piece) -> bool {
+                piece
+                    .cells()
+                    .into_iter()
+                    .all(|(x, y)| !self.is_occupied(x, y))
+            }
+
+            /// lowest position the piece can fall to
+            pub fn project(&self, piece: &Piece) -> Piece {
+                let mut projected = *piece;
+                while self.fits(&projected.shifted(0, -1)) {
+                    projected = projected.shifted(0, -1);
+                }
+
+                projected
+            }
+
+            pub fn place(&mut self, piece: &Piece) {
+                for (x, y) in piece.cells() {
+                    if let Some(index) = self.index(x, y) {
+                        self.cells[index] = Some(piece.kind);
+                    }
+                }
+            }
+
+            fn is_full_row(&self, y: usize) -> bool {
+                self.cells[y * self.width..(y + 1) * self.width]
+                    .iter()
+                    .all(Option::is_some)
+            }
+
+            /// removes full rows and shifts everything above down, returns
+            /// removed rows (bottom to top, as indexed before the
+            /// removal)
+            pub fn clear_lines(&mut self) -> Vec<usize> {
+                let rows = (0..self.height)
+                    .filter(|y| self.is_full_row(*y))
+                    .collect::<Vec<_>>();
+
+                if rows.is_empty() {
+                    return rows;
+                }
+
+                let mut cells = Vec::with_capacity(self.cells.len());
+                for (y, row) in self.cells.chunks(self.width).enumerate() {
+                    if !rows.contains(&y) {
+                        cells.extend_from_slice(row);
+                    }
+                }
+                cells.resize(self.width * self.height, None);
+
+                self.cells = cells;
+                rows
+            }
+
+            /// occupied cells as `(x, y, kind)`
+            pub fn iter(&self) -> impl Iterator<Item = (i32, i32, Kind)> + '_ {
+                self.cells.iter().enumerate().filter_map(|(index, cell)| {
+                    cell.map(|kind| {
+                        (
+                            (index % self.width) as i32,
+                            (index / self.width) as i32,
+                            kind,
+                        )
+                    })
+                })
+            }
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::super::piece::{Kind, Piece, Rotation};
+            use super::Board;
+
+            #[test]
+            fn fits_inside_the_walls_and_above_the_floor() {
+                let board = Board::new(10, 24);
+
+                assert!(board.fits(&Piece::new(Kind::T, 4, 10)));
+                assert!(!board.fits(&Piece::new(Kind::T, 0, 10)));
+                assert!(!board.fits(&Piece::new(Kind::T, 9, 10)));
+                assert!(!board.fits(&Piece::new(Kind::T, 4, -1)));
+                assert!(!board.fits(&Piece::new(Kind::I, 4, 23)));
+            }
+
+            #[test]
+            fn fits_around_placed_pieces() {
+                let mut board = Board::new(10, 24);
+                board.place(&Piece::new(Kind::O, 0, 0));
+
+                assert!(!board.fits(&Piece::new(Kind::O, 1, 0)));
+                assert!(board.fits(&Piece::new(Kind::O, 2, 0)));
+                assert!(board.fits(&Piece::new(Kind::O, 0, 2)));
+            }
+
+            #[test]
+            fn project_lands_on_the_floor_or_the_stack() {
+                let mut board = Board::new(10, 24);
+
+                let projected = board.project(&Piece::new(Kind::I, 4, 15));
+                assert_eq!(projected, Piece::new(Kind::I, 4, -1));
+
+                board.place(&projected);
+                let projected = board.project(&Piece::new(Kind::T, 4, 15));
+                assert_eq!(projected, Piece::new(Kind::T, 4, 1));
+
+                let vertical = Piece::new(Kind::I, 3, 15).rotated(Rotation::Right);
+                assert_eq!(board.project(&vertical).y, 2);
+            }
+
+            #[test]
+            fn place_fills_the_cells_of_the_piece() {
+                let mut board = Board::new(10, 24);
+                let piece = Piece::new(Kind::T, 4, 0);
+                board.place(&piece);
+
+                let cells: Vec<_> = board.iter().map(|(x, y, _)| (x, y)).collect();
+                assert_eq!(cells, [(3, 0), (4, 0), (5, 0), (4, 1)]);
+                assert!(board.iter().all(|(_, _, kind)| kind == Kind::T));
+                for (x, y) in piece.cells() {
+                    assert!(board.is_occupied(x, y));
+                }
+                assert!(!board.is_occupied(3, 1));
+            }
+
+            #[test]
+            fn place_drops_cells_out_of_the_board() {
+                let mut board = Board::new(10, 24);
+                board.place(&Piece::new(Kind::I, 4, 23));
+
+                assert_eq!(board.iter().count(), 0);
+            }
+
+            #[test]
+            fn clear_lines_removes_full_rows_and_shifts_down() {
+                let mut board = Board::new(4, 6);
+                board.place(&Piece::new(Kind::O, 0, 1));
+                board.place(&Piece::new(Kind::I, 1, -1));
+                board.place(&Piece::new(Kind::I, 1, 2));
+                board.place(&Piece::new(Kind::J, 1, 4));
+
+                assert_eq!(board.clear_lines(), [0, 3]);
+
+                let row = |y| {
+                    (0..4)
+                        .filter(|x| board.is_occupied(*x, y))
+                        .collect::<Vec<_>>()
+                };
+                assert_eq!(row(0), [0, 1]);
+                assert_eq!(row(1), [0, 1]);
+                assert_eq!(row(2), [0, 1, 2]);
+                assert_eq!(row(3), [0]);
+                assert!(row(4).is_empty());
+                assert!(board.clear_lines().is_empty());
+            }
+        }
+    }
+
+    /// super rotation system
+    pub mod srs {
+        use super::board::Board;
+        use super::piece::{Kind, Piece, Rotation};
+
+        /// tries each kick in order, `None` if no kick fits
+        pub fn rotate(board: &Board, piece: &Piece, to: Rotation) -> Option<Piece> {
+            kicks(piece.kind, piece.rotation, to)
+                .iter()
+                .map(|(dx, dy)| piece.rotated(to).shifted(*dx, *dy))
+                .find(|rotated| board.fits(rotated))
+        }
+
+        fn kicks(kind: Kind, from: Rotation, to: Rotation) -> &'static [(i32, i32)] {
+            use Rotation::*;
+
+            match (kind, from, to) {
+                (Kind::I, Spawn, Right) => &[(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+                (Kind::I, Right, Spawn) => &[(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+                (Kind::I, Right, Reverse) => &[(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+                (Kind::I, Reverse, Right) => &[(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+                (Kind::I, Reverse, Left) => &[(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+                (Kind::I, Left, Reverse) => &[(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+                (Kind::I, Left, Spawn) => &[(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+                (Kind::I, Spawn, Left) => &[(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+
+                (_, Spawn, Right) => &[(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+                (_, Right, Spawn) => &[(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+                (_, Right, Reverse) => &[(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+                (_, Reverse, Right) => &[(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+                (_, Reverse, Left) => &[(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+                (_, Left, Reverse) => &[(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+                (_, Left, Spawn) => &[(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+                (_, Spawn, Left) => &[(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+
+                // 180 spins are not in the guideline, these are from TETR.IO
+                (_, Spawn, Reverse) => &[(0, 0), (0, 1), (1, 1), (-1, 1), (1, 0), (-1, 0)],
+                (_, Reverse, Spawn) => &[(0, 0), (0, -1), (-1, -1), (1, -1), (-1, 0), (1, 0)],
+                (_, Right, Left) => &[(0, 0), (1, 0), (1, 2), (1, 1), (0, 2), (0, 1)],
+                (_, Left, Right) => &[(0, 0), (-1, 0), (-1, 2), (-1, 1), (0, 2), (0, 1)],
+
+                _ => &[(0, 0)],
+            }
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::super::board::Board;
+            use super::super::piece::{Kind, Piece, Rotation};
+            use super::rotate;
+
+            #[test]
+            fn rotates_in_place_when_it_fits() {
+                let board = Board::new(10, 24);
+                let piece = Piece::new(Kind::T, 4, 10);
+
+                let rotated = rotate(&board, &piece, Rotation::Right);
+                assert_eq!(rotated, Some(piece.rotated(Rotation::Right)));
+            }
+
+            #[test]
+            fn kicks_off_the_wall() {
+                let board = Board::new(10, 24);
+                let piece = Piece::new(Kind::T, 0, 10).rotated(Rotation::Right);
+                let to = Rotation::Reverse;
+                assert!(!board.fits(&piece.rotated(to)));
+
+                assert_eq!(
+                    rotate(&board, &piece, to),
+                    Some(piece.rotated(to).shifted(1, 0))
+                );
+            }
+
+            #[test]
+            fn kicks_the_i_by_its_own_table() {
+                let board = Board::new(10, 24);
+                let piece = Piece::new(Kind::I, 0, 10).rotated(Rotation::Left);
+
+                // the other pieces would kick by (-1, 0), off the board
+                let rotated = rotate(&board, &piece, Rotation::Spawn);
+                assert_eq!(rotated, Some(piece.rotated(Rotation::Spawn).shifted(1, 0)));
+            }
+
+            #[test]
+            fn fails_when_no_kick_fits() {
+                let board = Board::new(3, 2);
+                let piece = Piece::new(Kind::T, 1, 0);
+
+                assert_eq!(rotate(&board, &piece, Rotation::Right), None);
+            }
+        }
+    }
+
+    pub mod randomizer {
+        use std::collections::VecDeque;
+
+        use rand::rngs::StdRng;
+        use rand::seq::SliceRandom;
+        use rand::{Rng, SeedableRng};
+        use serde::{Deserialize, Serialize};
+
+        use super::piece::Kind;
+
+        pub trait Randomizer: Send + Sync {
+            fn next(&mut self) -> Kind;
+        }
+
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+        pub enum RandomizerKind {
+            Bag7,
+            Bag14,
+            Random,
+            History,
+        }
+        impl RandomizerKind {
+            pub fn build(&self, seed: u64) -> Box<dyn Randomizer> {
+                let rng = StdRng::seed_from_u64(seed);
+
+                match *self {
+                    Self::Bag7 => Box::new(Bag::new(rng, 1)),
+                    Self::Bag14 => Box::new(Bag::new(rng, 2)),
+                    Self::Random => Box::new(Random(rng)),
+                    Self::History => Box::new(History::new(rng)),
+                }
+            }
+        }
+
+        /// deals shuffled bags of each kind `copies` times
+        struct Bag {
+            rng: StdRng,
+            copies: usize,
+            bag: Vec<Kind>,
+        }
+        impl Bag {
+            fn new(rng: StdRng, copies: usize) -> Self {
+                Self {
+                    rng,
+                    copies,
+                    bag: vec![],
+                }
+            }
+        }
+        impl Randomizer for Bag {
+            fn next(&mut self) -> Kind {
+                if self.bag.is_empty() {
+                    self.bag = Kind::ALL.repeat(self.copies);
+                    self.bag.shuffle(&mut self.rng);
+                }
+
+                self.bag.pop().unwrap()
+            }
+        }
+
+        struct Random(StdRng);
+        impl Randomizer for Random {
+            fn next(&mut self) -> Kind { *Kind::ALL.choose(&mut self.0).unwrap() }
+        }
+
+        /// TGM2 style, rerolls up to 6 times while the kind is in the last 4
+        struct History {
+            rng: StdRng,
+            history: VecDeque<Kind>,
+            first: bool,
+        }
+        impl History {
+            const ROLLS: usize = 6;
+
+            fn new(rng: StdRng) -> Self {
+                Self {
+                    rng,
+                    history: VecDeque::from([Kind::Z, Kind::S, Kind::S, Kind::Z]),
+                    first: true,
+                }
+            }
+        }
+        impl Randomizer for History {
+            fn next(&mut self) -> Kind {
+                let kind = if self.first {
+                    // never starts with S, Z or O
+                    self.first = false;
+                    [Kind::I, Kind::J, Kind::L, Kind::T][self.rng.gen_range(0..4)]
+                } else {
+                    let mut kind = *Kind::ALL.choose(&mut self.rng).unwrap();
+                    for _ in 1..Self::ROLLS {
+                        if !self.history.contains(&kind) {
+                            break;
+                        }
+
+                        kind = *Kind::ALL.choose(&mut self.rng).unwrap();
+                    }
+
+                    kind
+                };
+
+                self.history.pop_front();
+                self.history.push_back(kind);
+
+                kind
+            }
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::RandomizerKind;
+            use crate::logic::piece::Kind;
+
+            const KINDS: [RandomizerKind; 4] = [
+                RandomizerKind::Bag7,
+                RandomizerKind::Bag14,
+                RandomizerKind::Random,
+                RandomizerKind::History,
+            ];
+
+            fn deal(kind: RandomizerKind, seed: u64, count: usize) -> Vec<Kind> {
+                let mut randomizer = kind.build(seed);
+                (0..count).map(|_| randomizer.next()).collect()
+            }
+
+            #[test]
+            fn same_seed_deals_the_same() {
+                for kind in KINDS {
+                    assert_eq!(deal(kind, 42, 100), deal(kind, 42, 100));
+                }
+            }
+
+            #[test]
+            fn bags_deal_each_kind_per_copy() {
+                for (kind, copies) in [(RandomizerKind::Bag7, 1), (RandomizerKind::Bag14, 2)] {
+                    let size = Kind::ALL.len() * copies;
+
+                    for seed in 0..10 {
+                        for bag in deal(kind, seed, size * 5).chunks(size) {
+                            for kind in Kind::ALL {
+                                let count = bag.iter().filter(|dealt| **dealt == kind).count();
+                                assert_eq!(count, copies);
+                            }
+                        }
+                    }
+                }
+            }
+
+            #[test]
+            fn history_never_starts_with_s_z_or_o() {
+                for seed in 0..100 {
+                    let first = deal(RandomizerKind::History, seed, 1)[0];
+                    assert!(![Kind::S, Kind::Z, Kind::O].contains(&first));
+                }
+            }
+        }
+    }
+
+    pub mod lock {
+        use std::time::Duration;
+
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+        pub struct LockDelay {
+            /// in milliseconds
+            pub delay: u64,
+            /// moves and spins to reset the delay on the ground, or infinity
+            pub resets: Option<u32>,
+        }
+
+        /// lock delay of a falling piece
+        pub struct Lock {
+            elapsed: Duration,
+            resets: u32,
+            /// has been on the ground since reaching the lowest row
+            touched: bool,
+            lowest: i32,
+        }
+        impl Lock {
+            pub fn new(y: i32) -> Self {
+                Self {
+                    elapsed: Duration::ZERO,
+                    resets: 0,
+                    touched: false,
+                    lowest: y,
+                }
+            }
+
+            /// true when the piece should lock
+            pub fn tick(
+                &mut self,
+                y: i32,
+                grounded: bool,
+                delta: Duration,
+                config: &LockDelay,
+            ) -> bool {
+                // falling to a new row gives back the resets
+                if y < self.lowest {
+                    self.lowest = y;
+                    self.resets = 0;
+                    self.elapsed = Duration::ZERO;
+                    self.touched = false;
+                }
+
+                if !grounded {
+                    return false;
+                }
+
+                self.touched = true;
+                self.elapsed += delta;
+
+                self.elapsed >= Duration::from_millis(config.delay)
+            }
+
+            /// after a move or spin
+            pub fn moved(&mut self, config: &LockDelay) {
+                if !self.touched {
+                    return;
+                }
+
+                match config.resets {
+                    Some(resets) if self.resets >= resets => {},
+                    _ => {
+                        self.resets += 1;
+                        self.elapsed = Duration::ZERO;
+                    },
+                }
+            }
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use std::time::Duration;
+
+            use super::{Lock, LockDelay};
+
+            const CONFIG: LockDelay = LockDelay {
+                delay: 500,
+                resets: Some(2),
+            };
+
+            fn ms(ms: u64) -> Duration { Duration::from_millis(ms) }
+
+            #[test]
+            fn locks_after_the_delay_on_the_ground() {
+                let mut lock = Lock::new(10);
+
+                assert!(!lock.tick(10, false, ms(1000), &CONFIG));
+                assert!(!lock.tick(10, true, ms(400), &CONFIG));
+                assert!(lock.tick(10, true, ms(100), &CONFIG));
+            }
+
+            #[test]
+            fn moves_reset_the_delay_up_to_the_limit() {
+                let mut lock = Lock::new(10);
+                lock.moved(&CONFIG);
+                assert!(!lock.tick(10, true, ms(400), &CONFIG));
+
+                lock.moved(&CONFIG);
+                assert!(!lock.tick(10, true, ms(400), &CONFIG));
+                lock.moved(&CONFIG);
+                assert!(!lock.tick(10, true, ms(400), &CONFIG));
+                // out of resets
+                lock.moved(&CONFIG);
+                assert!(lock.tick(10, true, ms(100), &CONFIG));
+            }
+
+            #[test]
+            fn falling_lower_gives_back_the_resets() {
+                let mut lock = Lock::new(10);
+                assert!(!lock.tick(10, true, ms(400), &CONFIG));
+                lock.moved(&CONFIG);
+                lock.moved(&CONFIG);
+                lock.moved(&CONFIG);
+
+                assert!(!lock.tick(9, true, ms(400), &CONFIG));
+                lock.moved(&CONFIG);
+                assert!(!lock.tick(9, true, ms(400), &CONFIG));
+            }
+
+            #[test]
+            fn resets_without_a_limit() {
+                let config = LockDelay {
+                    resets: None,
+                    ..CONFIG
+                };
+                let mut lock = Lock::new(10);
+
+                for _ in 0..100 {
+                    assert!(!lock.tick(10, true, ms(400), &config));
+                    lock.moved(&config);
+                }
+            }
+        }
+    }
+
+    pub mod repeat {
+        use std::time::Duration;
+
+        /// auto repeat of a held input
+        #[derive(Default)]
+        pub struct Repeat {
+            /// how long pressed, or released
+            held: Option<Duration>,
+        }
+        impl Repeat {
+            /// steps to take in this frame, `limit` steps at once for 0 arr,
+            /// and on the press too if das is 0 as well
+            ///
+            /// a press steps even if released in the same frame
+            pub fn tick(
+                &mut self,
+                presses: usize,
+                pressed: bool,
+                delta: Duration,
+                das: Duration,
+                arr: Duration,
+                limit: usize,
+            ) -> usize {
+                let instant = das.is_zero() && arr.is_zero();
+
+                if presses > 0 {
+                    self.held = pressed.then_some(Duration::ZERO);
+                    return if instant { limit } else { presses.min(limit) };
+                }
+                if !pressed {
+                    self.held = None;
+                    return 0;
+                }
+
+                // held through a stage transition
+                let Some(held) = self.held else {
+                    self.held = Some(Duration::ZERO);
+                    return if instant { limit } else { 1 };
+                };
+                let next = held + delta;
+                self.held = Some(next);
+
+                if next < das {
+                    return 0;
+                }
+                if arr.is_zero() {
+                    return limit;
+                }
+
+                // repeats at das, das + arr, das + arr * 2, ...
+                let repeats = |held: Duration| match held.checked_sub(das) {
+                    Some(over) => (over.as_nanos() / arr.as_nanos()) as usize + 1,
+                    None => 0,
+                };
+
+                (repeats(next) - repeats(held)).min(limit)
+            }
 
-        fn despawn_ui(mut commands: Commands, entities: Query<(Entity, &UiEntity)>) {
-            for (entity, _) in entities.iter() {
-                commands.entity(entity).despawn();
+            /// holds the repeat back for `delay`
+            pub fn cut(&mut self, das: Duration, delay: Duration) {
+                if let Some(held) = &mut self.held {
+                    *held = (*held).min(das.saturating_sub(delay));
+                }
             }
         }
 
-        #[derive(Component)]
-        struct UiEntity;
+        #[cfg(test)]
+        mod tests {
+            use std::time::Duration;
 
-        const BLOCK_SIZE: f32 = 48.0;
-        const AREA_SIZE: (f32, f32) = (BLOCK_SIZE * 10.0, BLOCK_SIZE * 16.0);
+            use super::Repeat;
 
-        fn spawn_area(mut commands: Commands) {
-            commands
-                .spawn()
-                .insert(AreaEntity)
-                .insert_bundle(OrthographicCameraBundle::new_2d());
+            const DAS: Duration = Duration::from_millis(100);
+            const ARR: Duration = Duration::from_millis(20);
 
-            commands
-                .spawn()
-                .insert(AreaEntity)
-                .insert(AreaFieldEntity)
-                .insert_bundle(SpriteBundle {
-                    sprite: Sprite {
-                        color: Color::BLACK,
-                        custom_size: Some(Vec2::new(
-                            AREA_SIZE.0 + BLOCK_SIZE,
-                            AREA_SIZE.1 + BLOCK_SIZE,
-                        )),
-                        ..default()
-                    },
-                    transform: Transform::from_xyz(0.0, 0.0, 0.0),
-                    ..default()
-                });
+            fn ms(ms: u64) -> Duration { Duration::from_millis(ms) }
 
-            for y in 0..=16 {
-                for x in [-1, 11].into_iter() {
-                    let (x, y) = transform_as_in_area(x as f32, y as f32);
+            #[test]
+            fn steps_once_on_press_then_repeats_after_das() {
+                let mut repeat = Repeat::default();
 
-                    commands
-                        .spawn()
-                        .insert(MinoEntity)
-                        .insert(DummyMinoEntity)
-                        .insert_bundle(SpriteBundle {
-                            sprite: Sprite {
-                                custom_size: Some(Vec2::new(0.0, 0.0)),
-                                ..default()
-                            },
-                            transform: Transform::from_xyz(x, y, -1.0),
-                            ..default()
-                        });
-                }
+                assert_eq!(repeat.tick(1, true, ms(16), DAS, ARR, 10), 1);
+                assert_eq!(repeat.tick(0, true, ms(50), DAS, ARR, 10), 0);
+                assert_eq!(repeat.tick(0, true, ms(50), DAS, ARR, 10), 1);
+                assert_eq!(repeat.tick(0, true, ms(45), DAS, ARR, 10), 2);
+                assert_eq!(repeat.tick(0, true, ms(1000), DAS, ARR, 10), 10);
             }
 
-            for x in -1..11 {
-                let (x, y) = transform_as_in_area(x as f32, -1.0);
+            #[test]
+            fn release_starts_over() {
+                let mut repeat = Repeat::default();
+                repeat.tick(1, true, ms(16), DAS, ARR, 10);
+                repeat.tick(0, true, ms(200), DAS, ARR, 10);
 
-                commands
-                    .spawn()
-                    .insert(MinoEntity)
-                    .insert(DummyMinoEntity)
-                    .insert_bundle(SpriteBundle {
-                        sprite: Sprite {
-                            custom_size: Some(Vec2::new(0.0, 0.0)),
-                            ..default()
-                        },
-                        transform: Transform::from_xyz(x, y, -1.0),
-                        ..default()
-                    });
+                assert_eq!(repeat.tick(0, false, ms(16), DAS, ARR, 10), 0);
+                assert_eq!(repeat.tick(1, true, ms(16), DAS, ARR, 10), 1);
+                assert_eq!(repeat.tick(0, true, ms(50), DAS, ARR, 10), 0);
             }
 
-            spawn_falling(commands);
-            // .with_children(|cb| {
-            //     let (x, y) = transform_as_in_area(5.0, 16.0);
-            //
-            //     cb.spawn()
-            //         .insert(FallingEntity)
-            //         .insert_bundle(SpriteBundle {
-            //             sprite: Sprite {
-            //                 color: Color::GREEN,
-            //                 custom_size: Some(Vec2::new(BLOCK_SIZE,
-            // BLOCK_SIZE)),                 ..default()
-            //             },
-            //             transform: Transform::from_translation(Vec3::new(x,
-            // y, 1.0)),             ..default()
-            //         });
-            // });
-            // for (x, y) in [(0.0, 0.0), (10.0, 0.0), (0.0, 16.0), (5.0, 8.0),
-            // (4.0, 9.0)] {     let (x, y) =
-            // transform_as_in_area(x, y);     cb.spawn()
-            //         .insert(AreaEntity)
-            //         .insert(MinoEntity)
-            //         .insert_bundle(SpriteBundle {
-            //             sprite: Sprite {
-            //                 color: Color::YELLOW_GREEN,
-            //                 custom_size: Some(Vec2::new(BLOCK_SIZE,
-            // BLOCK_SIZE)),                 ..default()
-            //             },
-            //             transform: Transform::from_translation(Vec3::new(x,
-            // y, 1.0)),             ..default()
-            //         });
-            // }
-        }
-
-        fn transform_as_in_area(x: f32, y: f32) -> (f32, f32) {
-            (
-                BLOCK_SIZE * x - AREA_SIZE.0 / 2.0,
-                BLOCK_SIZE * y - AREA_SIZE.1 / 2.0,
-            )
-        }
+            #[test]
+            fn tap_within_a_frame_steps_once() {
+                let mut repeat = Repeat::default();
 
-        fn untransform_as_in_area(x: f32, y: f32) -> (f32, f32) {
-            (
-                ((x + (AREA_SIZE.0 / 2.0)) / BLOCK_SIZE),
-                ((y + (AREA_SIZE.1 / 2.0)) / BLOCK_SIZE),
-            )
-        }
+                assert_eq!(repeat.tick(1, false, ms(16), DAS, ARR, 10), 1);
+                assert_eq!(repeat.tick(0, false, ms(16), DAS, ARR, 10), 0);
+            }
 
-        #[derive(Component)]
-        struct AreaFieldEntity;
+            #[test]
+            fn press_while_held_starts_over() {
+                let mut repeat = Repeat::default();
+                repeat.tick(1, true, ms(16), DAS, ARR, 10);
+                repeat.tick(0, true, ms(200), DAS, ARR, 10);
 
-        #[derive(Component)]
-        struct MinoEntity;
+                assert_eq!(repeat.tick(1, true, ms(16), DAS, ARR, 10), 1);
+                assert_eq!(repeat.tick(0, true, ms(50), DAS, ARR, 10), 0);
+            }
 
-        #[derive(Component)]
-        struct DummyMinoEntity;
+            #[test]
+            fn held_without_a_press_steps_once() {
+                let mut repeat = Repeat::default();
 
-        #[derive(Component)]
-        struct FallingEntity;
+                assert_eq!(repeat.tick(0, true, ms(16), DAS, ARR, 10), 1);
+                assert_eq!(repeat.tick(0, true, ms(50), DAS, ARR, 10), 0);
+            }
 
-        fn is_movable(
-            entities: &Query<(&MinoEntity, &Transform), Without<FallingEntity>>,
-            target: &Transform,
-        ) -> bool {
-            let [tx, ty, _] = target.translation.to_array();
-            let (tx, ty) = untransform_as_in_area(tx, ty);
+            #[test]
+            fn zero_arr_steps_to_the_limit() {
+                let mut repeat = Repeat::default();
 
-            for (_, transform) in entities.iter() {
-                let [x, y, _] = transform.translation.to_array();
-                let (x, y) = untransform_as_in_area(x, y);
+                assert_eq!(repeat.tick(1, true, ms(16), DAS, Duration::ZERO, 10), 1);
+                assert_eq!(repeat.tick(0, true, ms(50), DAS, Duration::ZERO, 10), 0);
+                assert_eq!(repeat.tick(0, true, ms(50), DAS, Duration::ZERO, 10), 10);
+            }
 
-                bevy::log::debug!("{} : {} | {} : {}", tx, x, ty, y); // magic code : slowing process?
+            #[test]
+            fn zero_das_and_arr_step_to_the_limit_on_press() {
+                let mut repeat = Repeat::default();
+                let zero = Duration::ZERO;
 
-                if tx.round() == x.round() && ty.round() == y.round() {
-                    return false;
-                }
+                assert_eq!(repeat.tick(1, false, ms(16), zero, zero, 10), 10);
+                assert_eq!(repeat.tick(1, true, ms(16), zero, zero, 10), 10);
+                assert_eq!(repeat.tick(0, true, ms(16), zero, zero, 10), 10);
             }
 
-            true
-        }
+            #[test]
+            fn cut_holds_the_repeat_back() {
+                let mut repeat = Repeat::default();
+                repeat.tick(1, true, ms(16), DAS, ARR, 10);
+                repeat.tick(0, true, ms(145), DAS, ARR, 10);
 
-        fn tick_falling(
-            mut stopwatch: Local<Stopwatch>,
-            time: Res<Time>,
-            mut entities: Query<(&FallingEntity, &mut Transform)>,
-            minos: Query<(&MinoEntity, &Transform), Without<FallingEntity>>,
-            mut landings: EventWriter<Landing>,
-        ) {
-            const THRESHOLD: f32 = 1.5;
+                repeat.cut(DAS, ms(30));
+                assert_eq!(repeat.tick(0, true, ms(20), DAS, ARR, 10), 0);
+                assert_eq!(repeat.tick(0, true, ms(10), DAS, ARR, 10), 1);
+            }
 
-            stopwatch.tick(time.delta());
+            #[test]
+            fn cut_does_nothing_while_released() {
+                let mut repeat = Repeat::default();
+                repeat.cut(DAS, ms(30));
 
-            if stopwatch.elapsed_secs() < THRESHOLD {
-                return;
+                assert_eq!(repeat.tick(1, true, ms(16), DAS, ARR, 10), 1);
             }
+        }
+    }
 
-            stopwatch.reset();
+    pub mod level {
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+        pub enum Gravity {
+            /// frames (1/60 s) to fall a row, less than 1 falls multiple rows a
+            /// frame
+            FramesPerRow(f32),
+            /// 20G, falls to the bottom at once
+            Instant,
+        }
 
-            for (_, mut transform) in entities.iter_mut() {
-                let [x, y, z] = transform.translation.to_array();
+        /// how soft drops accelerate the gravity
+        #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+        pub enum SoftDrop {
+            /// multiplies the gravity
+            Factor(f32),
+            /// falls to the bottom at once, without locking
+            Instant,
+        }
 
-                let new_transform = transform.with_translation(Vec3::new(x, y - BLOCK_SIZE, z));
+        /// gravity of each level from 1, the last one lasts for higher levels
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        #[serde(try_from = "Vec<Gravity>", into = "Vec<Gravity>")]
+        pub struct GravityTable(Vec<Gravity>);
+        impl GravityTable {
+            /// at least a level, each falling in a positive number of frames
+            pub fn new(gravity: Vec<Gravity>) -> Option<Self> {
+                let valid = |gravity: &Gravity| match *gravity {
+                    Gravity::FramesPerRow(frames) => frames.is_finite() && frames > 0.0,
+                    Gravity::Instant => true,
+                };
 
-                if !is_movable(&minos, &new_transform) {
-                    landings.send(Landing);
-                    continue;
-                }
+                (!gravity.is_empty() && gravity.iter().all(valid)).then_some(Self(gravity))
+            }
 
-                *transform = new_transform;
+            pub fn get(&self, level: u32) -> Gravity {
+                let index = (level.max(1) as usize - 1).min(self.0.len() - 1);
+                self.0[index]
             }
         }
+        impl Default for GravityTable {
+            // from the guideline: (0.8 - (level - 1) * 0.007) ^ (level - 1) secs per row
+            fn default() -> Self {
+                let mut gravity = (1..20)
+                    .map(|level| {
+                        let secs = (0.8 - (level - 1) as f32 * 0.007).powi(level - 1);
+                        Gravity::FramesPerRow(secs * 60.0)
+                    })
+                    .collect::<Vec<_>>();
+                gravity.push(Gravity::Instant);
+
+                Self(gravity)
+            }
+        }
+        impl TryFrom<Vec<Gravity>> for GravityTable {
+            type Error = &'static str;
 
-        struct Landing;
-
-        fn p90_spin(mut transform: Transform) -> Transform {
-            transform.rotate(Quat::from_rotation_z(std::f32::consts::PI / 2.0));
-            transform
+            fn try_from(gravity: Vec<Gravity>) -> Result<Self, Self::Error> {
+                Self::new(gravity).ok_or("gravity needs a level and positive frames per row")
+            }
+        }
+        impl From<GravityTable> for Vec<Gravity> {
+            fn from(table: GravityTable) -> Self { table.0 }
         }
 
-        fn n90_spin(mut transform: Transform) -> Transform {
-            transform.rotate(Quat::from_rotation_z(std::f32::consts::PI / -2.0));
-            transform
+        pub struct Progress {
+            lines_per_level: u32,
+            lines: u32,
         }
+        impl Progress {
+            pub fn new(lines_per_level: u32) -> Self {
+                Self {
+                    lines_per_level: lines_per_level.max(1),
+                    lines: 0,
+                }
+            }
 
-        // [+y]
-        // ^
-        // |
-        // + ---> [+x]
+            pub fn level(&self) -> u32 { self.lines / self.lines_per_level + 1 }
 
-        // [-- -- -- --]
-        // [   ++      ]
-        // [           ]
-        // [           ]
-        const I: [Transform; 4] = [
-            Transform::from_xyz(-1.0 * BLOCK_SIZE, 1.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(0.0 * BLOCK_SIZE, 1.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(1.0 * BLOCK_SIZE, 1.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(2.0 * BLOCK_SIZE, 1.0 * BLOCK_SIZE, 0.0),
-        ];
+            pub fn lines(&self) -> u32 { self.lines }
 
-        // [--         ]
-        // [-- ** --   ]
-        // [           ]
-        // [           ]
-        const J: [Transform; 4] = [
-            Transform::from_xyz(-1.0 * BLOCK_SIZE, 1.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(-1.0 * BLOCK_SIZE, 0.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(0.0 * BLOCK_SIZE, 0.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(1.0 * BLOCK_SIZE, 0.0 * BLOCK_SIZE, 0.0),
-        ];
+            pub fn add_lines(&mut self, lines: u32) { self.lines += lines; }
+        }
 
-        // [      --   ]
-        // [-- ** --   ]
-        // [           ]
-        // [           ]
-        const L: [Transform; 4] = [
-            Transform::from_xyz(1.0 * BLOCK_SIZE, 1.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(-1.0 * BLOCK_SIZE, 0.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(0.0 * BLOCK_SIZE, 0.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(1.0 * BLOCK_SIZE, 0.0 * BLOCK_SIZE, 0.0),
-        ];
+        #[cfg(test)]
+        mod tests {
+            use super::{Gravity, GravityTable, Progress};
 
-        // [  -- --    ]
-        // [  ** --    ]
-        // [           ]
-        // [           ]
-        const O: [Transform; 4] = [
-            Transform::from_xyz(0.0 * BLOCK_SIZE, 1.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(1.0 * BLOCK_SIZE, 1.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(0.0 * BLOCK_SIZE, 0.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(1.0 * BLOCK_SIZE, 0.0 * BLOCK_SIZE, 0.0),
-        ];
+            #[test]
+            fn gravity_table_needs_levels_of_positive_frames() {
+                assert!(GravityTable::new(Vec::new()).is_none());
 
-        // [   -- --   ]
-        // [-- **      ]
-        // [           ]
-        // [           ]
-        const S: [Transform; 4] = [
-            Transform::from_xyz(0.0 * BLOCK_SIZE, 1.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(1.0 * BLOCK_SIZE, 1.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(-1.0 * BLOCK_SIZE, 0.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(0.0 * BLOCK_SIZE, 0.0 * BLOCK_SIZE, 0.0),
-        ];
+                for frames in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+                    let gravity = vec![Gravity::FramesPerRow(60.0), Gravity::FramesPerRow(frames)];
+                    assert!(GravityTable::new(gravity).is_none());
+                }
 
-        // [   --      ]
-        // [-- ** --   ]
-        // [           ]
-        // [           ]
-        const T: [Transform; 4] = [
-            Transform::from_xyz(0.0 * BLOCK_SIZE, 1.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(-1.0 * BLOCK_SIZE, 0.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(0.0 * BLOCK_SIZE, 0.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(1.0 * BLOCK_SIZE, 0.0 * BLOCK_SIZE, 0.0),
-        ];
+                let gravity = vec![Gravity::FramesPerRow(0.5), Gravity::Instant];
+                assert!(GravityTable::new(gravity).is_some());
+            }
 
-        // [-- --      ]
-        // [   ** --   ]
-        // [           ]
-        // [           ]
-        const Z: [Transform; 4] = [
-            Transform::from_xyz(-1.0 * BLOCK_SIZE, 1.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(0.0 * BLOCK_SIZE, 1.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(0.0 * BLOCK_SIZE, 0.0 * BLOCK_SIZE, 0.0),
-            Transform::from_xyz(1.0 * BLOCK_SIZE, 0.0 * BLOCK_SIZE, 0.0),
-        ];
+            #[test]
+            fn gravity_table_lasts_for_higher_levels() {
+                let gravity = vec![Gravity::FramesPerRow(60.0), Gravity::FramesPerRow(30.0)];
+                let table = GravityTable::new(gravity).unwrap();
 
-        fn handle_landing(
-            mut commands: Commands,
-            landings: EventReader<Landing>,
-            parents: Query<(Entity, &FallingEntity, &Children), With<FallingEntity>>,
-            sprites: Query<(&Sprite, &GlobalTransform)>,
-        ) {
-            if landings.is_empty() {
-                return;
+                assert_eq!(table.get(0), Gravity::FramesPerRow(60.0));
+                assert_eq!(table.get(1), Gravity::FramesPerRow(60.0));
+                assert_eq!(table.get(2), Gravity::FramesPerRow(30.0));
+                assert_eq!(table.get(100), Gravity::FramesPerRow(30.0));
             }
 
-            for (parent, _, children) in parents.iter() {
-                commands.entity(parent).despawn_recursive();
-                for child in children.iter() {
-                    let (sprite, current_transform) = sprites.get(*child).unwrap();
-                    let sprite = sprite.clone();
+            #[test]
+            fn level_goes_up_every_lines_per_level() {
+                let mut progress = Progress::new(10);
+                assert_eq!(progress.level(), 1);
+
+                progress.add_lines(9);
+                assert_eq!(progress.level(), 1);
+                progress.add_lines(1);
+                assert_eq!(progress.level(), 2);
+                progress.add_lines(25);
+                assert_eq!(progress.level(), 4);
+                assert_eq!(progress.lines(), 35);
+            }
 
-                    commands
-                        .spawn()
-                        .insert(MinoEntity)
-                        .insert_bundle(SpriteBundle {
-                            sprite,
-                            transform: (*current_transform).into(),
-                            ..default()
-                        });
+            #[test]
+            fn zero_lines_per_level_counts_as_1() {
+                let mut progress = Progress::new(0);
+                progress.add_lines(3);
+
+                assert_eq!(progress.level(), 4);
+            }
+        }
+    }
+
+    pub mod score {
+        use serde::{Deserialize, Serialize};
+
+        /// points of a game mode, line clears and combos are multiplied by the
+        /// level
+        #[derive(Clone, Debug, Serialize, Deserialize)]
+        #[serde(default)]
+        pub struct ScoreTable {
+            /// single, double, triple and quad
+            pub clears: [u32; 4],
+            /// per row
+            pub soft_drop: u32,
+            /// per row
+            pub hard_drop: u32,
+            /// per combo count
+            pub combo: u32,
+            /// `(numerator, denominator)` applied to a quad following a quad
+            pub back_to_back: (u32, u32),
+        }
+        impl Default for ScoreTable {
+            fn default() -> Self {
+                Self {
+                    clears: [100, 300, 500, 800],
+                    soft_drop: 1,
+                    hard_drop: 2,
+                    combo: 50,
+                    back_to_back: (3, 2),
                 }
             }
+        }
 
-            spawn_falling(commands);
+        pub struct Scoring {
+            table: ScoreTable,
+            score: u32,
+            combo: Option<u32>,
+            back_to_back: bool,
         }
+        impl Scoring {
+            pub fn new(table: ScoreTable) -> Self {
+                Self {
+                    table,
+                    score: 0,
+                    combo: None,
+                    back_to_back: false,
+                }
+            }
 
-        fn spawn_falling(mut commands: Commands) {
-            let (transforms, color) = match rand::random::<u8>() % 7 {
-                0 => (I, Color::AQUAMARINE),
-                1 => (J, Color::BLUE),
-                2 => (L, Color::ORANGE),
-                3 => (O, Color::YELLOW),
-                4 => (S, Color::GREEN),
-                5 => (T, Color::PINK),
-                6 => (Z, Color::RED),
-                _ => panic!(),
-            };
+            pub fn score(&self) -> u32 { self.score }
 
-            let (x, y) = transform_as_in_area(5.0, 16.0);
+            pub fn drop(&mut self, rows: u32, hard: bool) {
+                let points = match hard {
+                    true => self.table.hard_drop,
+                    false => self.table.soft_drop,
+                };
 
-            commands
-                .spawn()
-                .insert(FallingEntity)
-                .insert_bundle(SpriteBundle {
-                    sprite: Sprite {
-                        color: Color::OLIVE,
-                        custom_size: Some(Vec2::new(10.0, 10.0)),
-                        ..default()
-                    },
-                    transform: Transform::from_xyz(x, y, 1.0),
-                    ..default()
-                })
-                .with_children(|cb| {
-                    for transform in transforms.into_iter() {
-                        cb.spawn().insert_bundle(SpriteBundle {
-                            transform,
-                            sprite: Sprite {
-                                color,
-                                custom_size: Some(Vec2::new(BLOCK_SIZE, BLOCK_SIZE)),
-                                ..default()
-                            },
-                            ..default()
-                        });
-                    }
-                });
-        }
+                self.score = self.score.saturating_add(points.saturating_mul(rows));
+            }
 
-        use crate::Config;
-        enum FallingInput {
-            Left,
-            Right,
-            HardDrop,
-            P90Spin,
-            N90Spin,
-        }
+            /// called on every lock, locking without clears breaks the combo
+            pub fn lock(&mut self, lines: usize, level: u32) {
+                if lines == 0 {
+                    self.combo = None;
+                    return;
+                }
 
-        fn falling_input(
-            key: Res<Input<KeyCode>>,
-            mut inputs: EventWriter<FallingInput>,
-            config: Res<Config>,
-        ) {
-            let config = &config.key.game;
+                let quad = lines >= 4;
+                let mut points = self.table.clears[lines.min(4) - 1].saturating_mul(level);
 
-            if key.just_pressed(config.left) {
-                inputs.send(FallingInput::Left);
-            } else if key.just_pressed(config.right) {
-                inputs.send(FallingInput::Right);
-            } else if key.just_pressed(config.hard_drop) {
-                inputs.send(FallingInput::HardDrop);
-            } else if key.just_pressed(config.p90_spin) {
-                inputs.send(FallingInput::P90Spin);
-            } else if key.just_pressed(config.n90_spin) {
-                inputs.send(FallingInput::N90Spin);
+                if quad && self.back_to_back {
+                    let (numerator, denominator) = self.table.back_to_back;
+                    // no bonus for a zero denominator, which the config rejects
+                    points = points
+                        .saturating_mul(numerator)
+                        .checked_div(denominator)
+                        .unwrap_or(points);
+                }
+                self.back_to_back = quad;
+
+                let combo = self.combo.map_or(0, |combo| combo.saturating_add(1));
+                self.combo = Some(combo);
+                points = points
+                    .saturating_add(self.table.combo.saturating_mul(combo).saturating_mul(level));
+
+                self.score = self.score.saturating_add(points);
             }
         }
 
-        fn falling_handle(
-            mut inputs: EventReader<FallingInput>,
-            mut entities: Query<(&FallingEntity, &mut Transform)>,
-            mut landings: EventWriter<Landing>,
-            minos: Query<(&MinoEntity, &Transform), Without<FallingEntity>>,
-        ) {
-            for input in inputs.iter() {
-                match *input {
-                    FallingInput::Left =>
-                        for (_, mut transform) in entities.iter_mut() {
-                            let [x, y, z] = transform.translation.to_array();
+        #[cfg(test)]
+        mod tests {
+            use super::{ScoreTable, Scoring};
 
-                            let new_transform =
-                                transform.with_translation(Vec3::new(x - BLOCK_SIZE, y, z));
+            fn scoring() -> Scoring { Scoring::new(ScoreTable::default()) }
 
-                            if is_movable(&minos, &new_transform) {
-                                *transform = new_transform;
-                            }
-                        },
-                    FallingInput::Right =>
-                        for (_, mut transform) in entities.iter_mut() {
-                            let [x, y, z] = transform.translation.to_array();
+            #[test]
+            fn clears_are_multiplied_by_the_level() {
+                let mut scoring = scoring();
+                scoring.lock(1, 1);
+                scoring.lock(0, 1);
+                scoring.lock(3, 2);
 
-                            let new_transform =
-                                transform.with_translation(Vec3::new(x + BLOCK_SIZE, y, z));
+                assert_eq!(scoring.score(), 100 + 500 * 2);
+            }
 
-                            if is_movable(&minos, &new_transform) {
-                                *transform = new_transform;
-                            }
-                        },
+            #[test]
+            fn back_to_back_quads_score_more() {
+                let mut scoring = scoring();
+                scoring.lock(4, 1);
+                scoring.lock(0, 1);
+                scoring.lock(4, 1);
+                assert_eq!(scoring.score(), 800 + 1200);
+
+                // broken by other clears, but not by locks without clears
+                scoring.lock(0, 1);
+                scoring.lock(1, 1);
+                scoring.lock(0, 1);
+                scoring.lock(4, 1);
+                assert_eq!(scoring.score(), 2000 + 100 + 800);
+            }
+
+            #[test]
+            fn combos_count_clears_in_a_row() {
+                let mut scoring = scoring();
+                scoring.lock(1, 1);
+                scoring.lock(1, 1);
+                scoring.lock(1, 2);
+                assert_eq!(scoring.score(), 100 + (100 + 50) + (200 + 50 * 2 * 2));
+
+                scoring.lock(0, 1);
+                scoring.lock(1, 1);
+                assert_eq!(scoring.score(), 650 + 100);
+            }
 
-                    FallingInput::HardDrop =>
-                        for (_, mut transform) in entities.iter_mut() {
-                            let [x, y, z] = transform.translation.to_array();
-                            let (ux, mut uy) = untransform_as_in_area(x, y);
+            #[test]
+            fn drops_score_per_row() {
+                let mut scoring = scoring();
+                scoring.drop(3, false);
+                scoring.drop(10, true);
 
-                            let mut new_transform;
-                            loop {
-                                let (x, y) = transform_as_in_area(ux, uy);
-                                new_transform = transform.with_translation(Vec3::new(x, y, z));
+                assert_eq!(scoring.score(), 3 + 20);
+            }
 
-                                if !is_movable(&minos, &new_transform) {
-                                    break;
-                                }
+            #[test]
+            fn zero_back_to_back_denominator_gives_no_bonus() {
+                let mut scoring = Scoring::new(ScoreTable {
+                    back_to_back: (3, 0),
+                    ..ScoreTable::default()
+                });
+                scoring.lock(4, 1);
+                scoring.lock(4, 1);
 
-                                uy -= 1.0;
-                            }
-                            *transform = new_transform;
+                assert_eq!(scoring.score(), 800 + (800 + 50));
+            }
 
-                            landings.send(Landing);
-                        },
+            #[test]
+            fn large_points_saturate() {
+                let mut scoring = Scoring::new(ScoreTable {
+                    clears: [u32::MAX; 4],
+                    hard_drop: u32::MAX,
+                    combo: u32::MAX,
+                    ..ScoreTable::default()
+                });
+                scoring.lock(4, u32::MAX);
+                scoring.lock(4, u32::MAX);
+                scoring.drop(u32::MAX, true);
 
-                    FallingInput::P90Spin =>
-                        for (_, mut transform) in entities.iter_mut() {
-                            *transform = p90_spin(*transform);
-                        },
-                    FallingInput::N90Spin =>
-                        for (_, mut transform) in entities.iter_mut() {
-                            *transform = n90_spin(*transform);
-                        },
-                };
+                assert_eq!(scoring.score(), u32::MAX);
             }
         }
+    }
+}
 
-        fn despawn_area(mut commands: Commands, entities: Query<(Entity, &AreaEntity)>) {
-            for (entity, _) in entities.iter() {
-                commands.entity(entity).despawn();
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use std::{env, fs, process};
+
+    use bevy::utils::HashMap;
+
+    use crate::logic::level::SoftDrop;
+    use crate::{Config, ConfigFile, FileError, Language, Message};
+
+    /// a file in the temporary directory, removed when dropped
+    struct TempFile(PathBuf);
+    impl TempFile {
+        fn new(name: &str) -> Self {
+            Self(env::temp_dir().join(format!("karpas-{}-{}.ron", name, process::id())))
+        }
+
+        fn with(name: &str, text: &str) -> Self {
+            let file = Self::new(name);
+            fs::write(&file.0, text).unwrap();
+            file
+        }
+
+        fn load(&self) -> Result<Config, FileError> { ConfigFile::new(Some(self.0.clone())).load() }
+    }
+    impl Drop for TempFile {
+        fn drop(&mut self) { let _ = fs::remove_file(&self.0); }
+    }
+
+    #[test]
+    fn catalogs_have_every_message() {
+        for language in [Language::En, Language::Ja] {
+            let catalog: HashMap<Message, String> = ron::from_str(language.catalog()).unwrap();
+
+            for message in Message::ALL {
+                assert!(
+                    catalog.contains_key(&message),
+                    "{:?} misses {:?}",
+                    language,
+                    message
+                );
             }
         }
+    }
 
-        #[derive(Component)]
-        struct AreaEntity;
+    #[test]
+    fn missing_config_is_the_default() {
+        let config = TempFile::new("missing").load().unwrap();
+
+        assert!(config.view.ghost);
+        assert_eq!(config.rule.previews, 5);
+        assert_eq!(config.pad.stick_threshold, 0.5);
     }
 
-    pub mod end {
-        use bevy::app::{AppExit, Plugin as PluginTrait};
-        use bevy::prelude::*;
+    #[test]
+    fn partial_config_is_filled_with_the_defaults() {
+        let file = TempFile::with("partial", "(view: (ghost: false), rule: (previews: 3))");
+        let config = file.load().unwrap();
 
-        use crate::Stage::End as SelfStage;
+        assert!(!config.view.ghost);
+        assert_eq!(config.rule.previews, 3);
+        assert_eq!(config.rule.lines_per_level, 10);
+        assert_eq!(config.rule.soft_drop, SoftDrop::Factor(20.0));
+    }
 
-        pub struct Plugin;
-        impl PluginTrait for Plugin {
-            fn name(&self) -> &str { "end" }
+    #[test]
+    fn old_font_path_is_the_regular_weight() {
+        let file = TempFile::with("font", r#"(path: (font: "fonts/old.ttf"))"#);
+        let config = file.load().unwrap();
 
-            fn build(&self, app: &mut App) {
-                app.add_system_set(SystemSet::on_enter(SelfStage).with_system(stop_app));
-            }
-        }
+        assert_eq!(config.path.fonts.regular, "fonts/old.ttf");
+        assert!(config.path.font.is_empty());
+        assert_eq!(
+            config.path.fonts.bold,
+            "fonts/zkgn/ZenKakuGothicNew-Bold.ttf"
+        );
+    }
 
-        fn stop_app(mut exit: EventWriter<AppExit>) { exit.send(AppExit); }
+    #[test]
+    fn invalid_configs_are_rejected() {
+        let files = [
+            TempFile::with("soft-drop", "(rule: (soft_drop: Factor(0.0)))"),
+            TempFile::with("back-to-back", "(rule: (score: (back_to_back: (3, 0))))"),
+            TempFile::with("gravity", "(rule: (gravity: []))"),
+            TempFile::with("stick", "(pad: (stick_threshold: 0.0))"),
+            TempFile::with("stick-over", "(pad: (stick_threshold: 1.5))"),
+        ];
+
+        for file in &files {
+            assert!(matches!(
+                file.load(),
+                Err(FileError::Invalid(..) | FileError::Parse(..))
+            ));
+        }
     }
 }